@@ -20,7 +20,7 @@ module.exports = async (orchestrator) => {
     const [bobbo_chat] = bobbo_chat_happ.cells;
 
     // Create a channel
-    const channel_uuid = uuidv4();
+    const channel_uuid = await alice_chat.call("chat", "new_channel_uuid", null);
     const channel = await alice_chat.call("chat", "create_channel", {
       name: "Test Channel",
       entry: { category: "General", uuid: channel_uuid },
@@ -276,7 +276,7 @@ const setup = async (s: ScenarioApi, t, config, local): Promise<{ playerAgents,
     }
 
     console.log(`Creating channel for test:`)
-    const channel_uuid = uuidv4();
+    const channel_uuid = await playerAgents[0][0].cell.call('chat', 'new_channel_uuid', null);
     const entry = { category: "General", uuid: channel_uuid }
     const createChannelResult = await playerAgents[0][0].cell.call('chat', 'create_channel', { name: `Test Channel`, entry });
     console.log(createChannelResult);
@@ -292,19 +292,11 @@ const send = async (i, cell, channel, signal: "signal" | "noSignal") => {
             uuid: uuidv4(),
             content: `message ${i}`,
         },
+        signal: signal === "signal",
     }
     console.log(`creating message ${i}`)
-    const messageData = await cell.call('chat', 'create_message', msg)
+    await cell.call('chat', 'create_message', msg)
     console.log(`message created ${i}`)
-
-    if (signal === "signal") {
-        console.log(`sending signal ${i}`)
-        const r = await cell.call('chat', 'signal_chatters', {
-            messageData,
-            channelData: channel,
-        })
-        console.log(`signal sent ${i}`)
-    }
 }
 
 const sendSerially = async (end: number, sendingCell: Cell, channel, messagesToSend: number) => {
@@ -18,8 +18,13 @@ module.exports = async (orchestrator) => {
       content: "Hello from alice :)",
     }
     let receivedCount = 0
+    let channelSignals: any[] = []
     bob.setSignalHandler((signal) => {
       console.log("Received Signal:", signal)
+      if (signal.data.payload.signal_name === 'Channel') {
+        channelSignals.push(signal.data.payload.signal_payload)
+        return
+      }
       t.deepEqual(signal.data.payload.signal_payload.messageData.entry, MESSAGE)
       receivedCount += 1
     })
@@ -39,10 +44,18 @@ module.exports = async (orchestrator) => {
     await alice_chat.call('chat', 'refresh_chatter', null);
 
     // Create a channel
-    const channel_uuid = uuidv4();
+    const channel_uuid = await alice_chat.call('chat', 'new_channel_uuid', null);
     const channel = await alice_chat.call('chat', 'create_channel', { name: "Test Channel", entry: { category: "General", uuid: channel_uuid } });
     console.log("CHANNEL: >>>", channel);
 
+    // bob hears about the new channel because he's an active chatter
+    for (let i = 0; i < 5; i++) {
+      if (channelSignals.length > 0) break;
+      console.log(`waiting for channel signal: ${i}`)
+      await delay(500)
+    }
+    t.deepEqual(channelSignals, [channel])
+
     const msg1 = {
       last_seen: { First: null },
       channel: channel.entry,
@@ -59,7 +72,8 @@ module.exports = async (orchestrator) => {
 
     const r4 = await alice_chat.call('chat', 'signal_chatters', signalMessageData);
     t.equal(r4.total, 2)
-    t.equal(r4.sent.length, 1)
+    t.equal(r4.sent_count, 1)
+    t.equal(r4.sent, null)
 
     // waiting for the signal to be received by bob.
     for (let i = 0; i < 5; i++) {
@@ -123,5 +137,70 @@ module.exports = async (orchestrator) => {
     }
     // bob should now have gotten a 3rd signal because he's an active chatter and we included active chatters
     t.equal(receivedCount, 3)
+
+    // create_message can signal the active chatters itself
+    const r5 = await alice_chat.call('chat', 'create_message', { ...msg1, signal: true });
+    t.deepEqual(r5.entry, msg1.entry)
+    t.equal(r5.sigResults.total, 2)
+    t.equal(r5.sigResults.sent_count, 1)
+    for (let i = 0; i < 5; i++) {
+      if (receivedCount > 3) break;
+      console.log(`waiting for signal: ${i}`)
+      await delay(500)
+    }
+    t.equal(receivedCount, 4)
+
+    // recipients are only listed when asked for, and relaying reaches the same audience
+    const r6 = await alice_chat.call('chat', 'signal_chatters_with_options', {
+      signal_message_data: signalMessageData,
+      options: { detailed: true, relay: true },
+    });
+    t.deepEqual(r6.sent, [Codec.AgentId.encode(bob_chat.cellId[1])])
+    for (let i = 0; i < 5; i++) {
+      if (receivedCount > 4) break;
+      console.log(`waiting for signal: ${i}`)
+      await delay(500)
+    }
+    t.equal(receivedCount, 5)
+
+    // a message claiming to be from someone other than the sender is dropped
+    await alice_chat.call('chat', 'signal_specific_chatters', {
+      signal_message_data: {
+        ...signalMessageData,
+        messageData: { ...r1, createdBy: bob_chat.cellId[1] },
+      },
+      chatters: [bob_chat.cellId[1]]
+    })
+    for (let i = 0; i < 5; i++) {
+      if (receivedCount > 5) break;
+      console.log(`waiting for signal: ${i}`)
+      await delay(500)
+    }
+    t.equal(receivedCount, 5)
+
+    // renaming and archiving the channel are signalled too
+    const renamed = await alice_chat.call('chat', 'rename_channel', { channel: channel.entry, name: "Renamed Channel" });
+    const archived = await alice_chat.call('chat', 'archive_channel', channel.entry);
+    for (let i = 0; i < 5; i++) {
+      if (channelSignals.length > 2) break;
+      console.log(`waiting for channel signal: ${i}`)
+      await delay(500)
+    }
+    t.deepEqual(channelSignals, [channel, renamed, archived])
+    t.equal(archived.info.name, "Renamed Channel")
+    t.ok(archived.info.archived)
+
+    // bob stops listening to every channel
+    await bob_chat.call('chat', 'set_signal_filter', { channels: [] })
+    await alice_chat.call('chat', 'signal_specific_chatters', {
+      signal_message_data: signalMessageData,
+      chatters: [bob_chat.cellId[1]]
+    })
+    for (let i = 0; i < 5; i++) {
+      if (receivedCount > 5) break;
+      console.log(`waiting for signal: ${i}`)
+      await delay(500)
+    }
+    t.equal(receivedCount, 5)
   })
 }
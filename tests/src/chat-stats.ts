@@ -25,7 +25,7 @@ module.exports = async (orchestrator) => {
     await alice_chat.call('chat', 'refresh_chatter', null);
 
     // Create a channel
-    const channel_uuid = uuidv4();
+    const channel_uuid = await alice_chat.call('chat', 'new_channel_uuid', null);
     const channel = await alice_chat.call('chat', 'create_channel', { name: "Test Channel", entry: { category: "General", uuid: channel_uuid } });
     console.log("CHANNEL: >>>", channel.entry);
 
@@ -54,7 +54,7 @@ module.exports = async (orchestrator) => {
     const r2 = await alice_chat.call('chat', 'create_message', msg2);
     t.deepEqual(r2.entry, msg2.entry);
 
-    const channel_uuid2 = uuidv4();
+    const channel_uuid2 = await alice_chat.call('chat', 'new_channel_uuid', null);
     const channel2 = await alice_chat.call('chat', 'create_channel', { name: "Test2 Channel", entry: { category: "General", uuid: channel_uuid2 } });
 
     const msg3 = {
@@ -47,6 +47,12 @@ export const networkedConductorConfig = Config.gen({ network: NETWORK })
 
 export const delay = ms => new Promise(r => setTimeout(r, ms))
 
+// Chat zome errors arrive as `Guest("<json envelope>")`, return the envelope's code
+export const chatErrorCode = (e) => {
+  const guest = e.data.data.match(/Guest\((".*")\)$/)
+  return guest ? JSON.parse(JSON.parse(guest[1])).code : undefined
+}
+
 export const awaitIntegration = async (cell) => {
   while (true) {
     const dump = await cell.stateDump()
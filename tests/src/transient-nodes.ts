@@ -60,7 +60,7 @@ const doTransientNodes = async (s, t, local) => {
   }
 
   // Create a channel
-  const channel_uuid = uuidv4();
+  const channel_uuid = await alice_chat.call('chat', 'new_channel_uuid', null);
   const channel = await alice_chat.call('chat', 'create_channel', { name: "Test Channel", entry: { category: "General", uuid: channel_uuid } });
 
   const msg1 = {
@@ -110,6 +110,141 @@ fn append_message_links_recursive(
     Ok(())
 }
 
+/// Returns the links of every message in the hour of `since` or later.
+///
+/// Walks down the tree from the channel, skipping any subtree
+/// that ends before the hour `since` falls in.
+pub fn get_message_links_since(channel: Path, since: Timestamp) -> ChatResult<Vec<Link>> {
+    let root_path_length = channel.as_ref().len();
+    let since_path = timestamp_into_path(channel.clone(), since)?;
+    let since_segments = since_path.as_ref()[root_path_length..]
+        .iter()
+        .map(segment_from_component)
+        .collect::<ChatResult<Vec<_>>>()?;
+
+    let mut links = Vec::new();
+    if channel.exists()? {
+        append_message_links_since(
+            channel.path_entry_hash()?,
+            &since_segments,
+            true,
+            &mut links,
+        )?;
+    }
+    Ok(links)
+}
+
+fn append_message_links_since(
+    base: EntryHash,
+    since_segments: &[i32],
+    on_since_boundary: bool,
+    links: &mut Vec<Link>,
+) -> ChatResult<()> {
+    match since_segments.split_first() {
+        // We are at an hour bucket so these are the message links
+        None => links.append(&mut get_links(base, None)?),
+        Some((since_segment, rest)) => {
+            let children = get_links(base, None)?
+                .into_iter()
+                .filter_map(|l| path_component_from_link(&l).ok().map(|c| (c, l))) // filter out non-path links
+                .filter_map(|(c, l)| segment_from_component(&c).ok().map(|s| (s, l)));
+            for (segment, link) in children {
+                // Only the subtrees on the boundary can hold anything earlier than `since`
+                if on_since_boundary && segment < *since_segment {
+                    continue;
+                }
+                append_message_links_since(
+                    link.target,
+                    rest,
+                    on_since_boundary && segment == *since_segment,
+                    links,
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns the message links in the newest hour bucket of a channel that has any.
+///
+/// Walks down the tree always taking the latest child first,
+/// so usually this only costs one `get_links` per level.
+pub fn get_newest_message_links(channel: Path) -> ChatResult<Vec<Link>> {
+    newest_message_links(channel.path_entry_hash()?, BUCKET_DEPTH)
+}
+
+fn newest_message_links(base: EntryHash, depth: usize) -> ChatResult<Vec<Link>> {
+    // We are at an hour bucket so these are the message links
+    if depth == 0 {
+        return Ok(get_links(base, None)?);
+    }
+    let mut children = get_links(base, None)?
+        .into_iter()
+        .filter_map(|l| path_component_from_link(&l).ok().map(|c| (c, l))) // filter out non-path links
+        .filter_map(|(c, l)| segment_from_component(&c).ok().map(|s| (s, l)))
+        .collect::<Vec<_>>();
+    children.sort_unstable_by_key(|(segment, _)| cmp::Reverse(*segment));
+    for (_, link) in children {
+        let links = newest_message_links(link.target, depth - 1)?;
+        if !links.is_empty() {
+            return Ok(links);
+        }
+    }
+    Ok(Vec::new())
+}
+
+/// The time the newest message was linked on each channel, in the order of the channel path hashes.
+///
+/// Walks down the trees of every channel together with one batch of `get_links` per level,
+/// always taking the latest child. Only a channel whose latest hour bucket turns out empty
+/// goes on to look at its other buckets with `get_newest_message_links`.
+pub fn get_newest_message_times(channels: Vec<EntryHash>) -> ChatResult<Vec<Option<Timestamp>>> {
+    let mut bases: Vec<Option<EntryHash>> = channels.iter().cloned().map(Some).collect();
+    for _ in 0..BUCKET_DEPTH {
+        bases = batch_get_links(&bases)?
+            .into_iter()
+            .map(|links| {
+                links?
+                    .into_iter()
+                    .filter_map(|l| path_component_from_link(&l).ok().map(|c| (c, l))) // filter out non-path links
+                    .filter_map(|(c, l)| segment_from_component(&c).ok().map(|s| (s, l)))
+                    .max_by_key(|(segment, _)| *segment)
+                    .map(|(_, link)| link.target)
+            })
+            .collect();
+    }
+    let newest = |links: &[Link]| links.iter().map(|link| link.timestamp).max();
+    channels
+        .into_iter()
+        .zip(batch_get_links(&bases)?)
+        .map(|(channel, links)| match links {
+            // The channel has no buckets so no messages
+            None => Ok(None),
+            Some(links) if !links.is_empty() => Ok(newest(&links)),
+            Some(_) => Ok(newest(&newest_message_links(channel, BUCKET_DEPTH)?)),
+        })
+        .collect()
+}
+
+/// `get_links` on every base in one batch, `None` where there is no base
+fn batch_get_links(bases: &[Option<EntryHash>]) -> ChatResult<Vec<Option<Vec<Link>>>> {
+    let inputs: Vec<GetLinksInput> = bases
+        .iter()
+        .flatten()
+        .map(|base| GetLinksInput::new(base.clone(), None))
+        .collect();
+    let mut all_links = if inputs.is_empty() {
+        Vec::new()
+    } else {
+        HDK.with(|hdk| hdk.borrow().get_links(inputs))?
+    }
+    .into_iter();
+    Ok(bases
+        .iter()
+        .map(|base| base.as_ref().and_then(|_| all_links.next()))
+        .collect())
+}
+
 fn path_component_from_link(link: &Link) -> Result<Component, SerializedBytesError> {
     SerializedBytes::from(UnsafeBytes::from(link.tag.clone().into_inner())).try_into()
 }
@@ -127,6 +262,9 @@ pub fn last_segment_from_path(path: &Path) -> ChatResult<i32> {
     segment_from_component(component)
 }
 
+/// How many segments `timestamp_into_path` adds below the channel
+const BUCKET_DEPTH: usize = 4;
+
 /// Add the message from the Date type to this path
 pub fn timestamp_into_path(path: Path, time: Timestamp) -> ChatResult<Path> {
     let (ms, ns) = time.as_seconds_and_nanos();
@@ -0,0 +1,56 @@
+//! Agents we don't want to hear from.
+//!
+//! The block list is private to our source chain, so blocked agents and moderators can't see it.
+//! Content from blocked agents is dropped when we list messages or chatters
+//! and when their signals reach us, before any of it gets to the UI.
+use crate::error::ChatResult;
+use hdk::prelude::*;
+
+/// The agents we have blocked, saved privately on the source chain.
+/// Each change commits a new list and the latest one wins.
+#[hdk_entry(id = "block_list", visibility = "private")]
+#[derive(Clone, PartialEq, Eq, Default)]
+pub struct BlockList {
+    pub agents: Vec<AgentPubKey>,
+}
+
+impl BlockList {
+    pub fn is_blocked(&self, agent: &AgentPubKey) -> bool {
+        self.agents.contains(agent)
+    }
+}
+
+/// The latest block list on our chain
+pub(crate) fn get_block_list() -> ChatResult<BlockList> {
+    let filter = QueryFilter::new()
+        .entry_type(entry_type!(BlockList)?)
+        .include_entries(true);
+    let latest = query(filter)?
+        .into_iter()
+        .max_by_key(|element| element.header().header_seq());
+    match latest {
+        Some(element) => Ok(element
+            .entry()
+            .to_app_option::<BlockList>()?
+            .unwrap_or_default()),
+        None => Ok(BlockList::default()),
+    }
+}
+
+pub(crate) fn block_agent(agent: AgentPubKey) -> ChatResult<BlockList> {
+    let mut list = get_block_list()?;
+    if !list.is_blocked(&agent) {
+        list.agents.push(agent);
+        create_entry(&list)?;
+    }
+    Ok(list)
+}
+
+pub(crate) fn unblock_agent(agent: AgentPubKey) -> ChatResult<BlockList> {
+    let mut list = get_block_list()?;
+    if list.is_blocked(&agent) {
+        list.agents.retain(|a| *a != agent);
+        create_entry(&list)?;
+    }
+    Ok(list)
+}
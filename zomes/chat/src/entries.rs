@@ -1,2 +1,3 @@
+pub mod category;
 pub mod channel;
 pub mod message;
@@ -0,0 +1,74 @@
+use crate::timestamp::Timestamp;
+use hdk::prelude::*;
+pub mod handlers;
+
+/// The name and place in the sidebar of a category.
+/// The category itself is the string in `Channel.category` and never changes,
+/// so renaming or moving a category links a new info.
+#[hdk_entry(id = "category_info")]
+#[derive(Clone, PartialEq, Eq)]
+pub struct CategoryInfo {
+    pub category: String,
+    pub name: String,
+    /// Categories are listed by position, then by name
+    pub position: u32,
+    pub created_by: AgentPubKey,
+    pub created_at: Timestamp,
+    /// The category link this info replaces, `None` for the category's first info
+    #[serde(default)]
+    pub previous: Option<HeaderHash>,
+}
+
+/// Input to the create category call
+#[derive(Debug, Serialize, Deserialize, SerializedBytes)]
+pub struct CategoryInput {
+    pub category: String,
+    pub name: String,
+    /// Defaults to after every other category
+    pub position: Option<u32>,
+}
+
+/// Input to the rename category call
+#[derive(Debug, Serialize, Deserialize, SerializedBytes)]
+pub struct RenameCategoryInput {
+    pub category: String,
+    pub name: String,
+}
+
+/// Input to the move category call
+#[derive(Debug, Serialize, Deserialize, SerializedBytes)]
+pub struct MoveCategoryInput {
+    pub category: String,
+    pub position: u32,
+}
+
+/// The categories returned from list categories, in sidebar order
+#[derive(Debug, Serialize, Deserialize, SerializedBytes, derive_more::From)]
+pub struct CategoryList {
+    pub categories: Vec<CategoryInfo>,
+}
+
+/// Tags the links from the categories root to the infos of each category.
+/// The tag ends with the category's path hash so the latest info of every
+/// category can be picked out of a single `get_links` on the root.
+pub(crate) struct CategoryTag;
+
+impl CategoryTag {
+    const TAG: &'static [u8; 8] = b"category";
+
+    /// The prefix every category tag starts with
+    pub(crate) fn any() -> LinkTag {
+        LinkTag::new(*Self::TAG)
+    }
+
+    /// Create the tag for the category with this path hash
+    pub(crate) fn tag(category_hash: &EntryHash) -> LinkTag {
+        LinkTag::new([&Self::TAG[..], category_hash.get_raw_39()].concat())
+    }
+
+    /// The category path hash in a category tag, or `None` for any other tag
+    pub(crate) fn category_hash(tag: &LinkTag) -> Option<EntryHash> {
+        let bytes = tag.0.strip_prefix(&Self::TAG[..])?;
+        EntryHash::from_raw_39(bytes.to_vec()).ok()
+    }
+}
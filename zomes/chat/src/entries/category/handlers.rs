@@ -0,0 +1,210 @@
+use super::{
+    CategoryInfo, CategoryInput, CategoryList, CategoryTag, MoveCategoryInput, RenameCategoryInput,
+};
+use crate::{
+    channel::handlers::category_path,
+    error::{ChatError, ChatResult},
+    validation::MAX_CATEGORY_NAME_LENGTH,
+};
+use hdk::prelude::*;
+use std::collections::HashMap;
+
+/// The category of every channel from before categories were registered,
+/// when clients put all channels in it. It is always listed so those channels aren't lost.
+pub const DEFAULT_CATEGORY: &str = "General";
+
+/// Every category is linked from here
+pub fn categories_path() -> Path {
+    Path::from("categories")
+}
+
+/// Register a category so it shows up in `list_categories`.
+/// Creating a channel registers its category if nobody has yet.
+pub(crate) fn create_category(input: CategoryInput) -> ChatResult<CategoryInfo> {
+    let CategoryInput {
+        category,
+        name,
+        position,
+    } = input;
+    if get_category_info(&category)?.is_some() {
+        return Err(ChatError::CategoryExists(category));
+    }
+    let position = match position {
+        Some(position) => position,
+        None => list_categories()?
+            .categories
+            .iter()
+            .map(|info| info.position.saturating_add(1))
+            .max()
+            .unwrap_or(0),
+    };
+    category_path(&category).ensure()?;
+    categories_path().ensure()?;
+    commit_category_info(category, name, position, None)
+}
+
+/// Register a channel's category with its own string as the name, unless it is registered already.
+/// The name is cut to the longest a category name may be.
+pub(crate) fn ensure_category(category: &str) -> ChatResult<()> {
+    if get_category_info(category)?.is_some() {
+        return Ok(());
+    }
+    create_category(CategoryInput {
+        category: category.to_string(),
+        name: category.chars().take(MAX_CATEGORY_NAME_LENGTH).collect(),
+        position: None,
+    })?;
+    Ok(())
+}
+
+/// Rename a category by linking a new info with the new name.
+/// Only the author of the current info may rename it.
+pub(crate) fn rename_category(input: RenameCategoryInput) -> ChatResult<CategoryInfo> {
+    let RenameCategoryInput { category, name } = input;
+    let (current_link, current) = get_existing_category_info(&category)?;
+    check_category_author(&current)?;
+    commit_category_info(category, name, current.position, Some(current_link))
+}
+
+/// Move a category by linking a new info with the new position.
+/// Only the author of the current info may move it.
+pub(crate) fn move_category(input: MoveCategoryInput) -> ChatResult<CategoryInfo> {
+    let MoveCategoryInput { category, position } = input;
+    let (current_link, current) = get_existing_category_info(&category)?;
+    check_category_author(&current)?;
+    commit_category_info(category, current.name, position, Some(current_link))
+}
+
+/// Commit a new category info and link it from the categories root.
+/// `previous` is the category link the new info replaces.
+fn commit_category_info(
+    category: String,
+    name: String,
+    position: u32,
+    previous: Option<HeaderHash>,
+) -> ChatResult<CategoryInfo> {
+    let category_hash = category_path(&category).path_entry_hash()?;
+    let info = CategoryInfo {
+        category,
+        name,
+        position,
+        // This agent
+        created_by: agent_info()?.agent_initial_pubkey,
+        // Right now
+        created_at: sys_time()?,
+        previous,
+    };
+    create_entry(&info)?;
+    create_link(
+        categories_path().path_entry_hash()?,
+        hash_entry(&info)?,
+        HdkLinkType::Any,
+        CategoryTag::tag(&category_hash),
+    )?;
+    Ok(info)
+}
+
+fn check_category_author(info: &CategoryInfo) -> ChatResult<()> {
+    if info.created_by != agent_info()?.agent_initial_pubkey {
+        return Err(ChatError::NotCategoryAuthor);
+    }
+    Ok(())
+}
+
+fn get_existing_category_info(category: &str) -> ChatResult<(HeaderHash, CategoryInfo)> {
+    get_latest_category_info(category)?
+        .ok_or_else(|| ChatError::MissingCategory(category.to_string()))
+}
+
+/// The latest info of a category, if it was registered
+pub(crate) fn get_category_info(category: &str) -> ChatResult<Option<CategoryInfo>> {
+    Ok(get_latest_category_info(category)?.map(|(_, info)| info))
+}
+
+/// The latest info of a category linked by its owner, with the header of its link
+fn get_latest_category_info(category: &str) -> ChatResult<Option<(HeaderHash, CategoryInfo)>> {
+    let category_hash = category_path(category).path_entry_hash()?;
+    let links = get_link_details(
+        categories_path().path_entry_hash()?,
+        Some(CategoryTag::tag(&category_hash)),
+    )?;
+    let (header_hash, latest_info) = match owner_category_links(links).remove(&category_hash) {
+        Some(mut links) => match links.pop() {
+            Some(latest) => latest,
+            None => return Ok(None),
+        },
+        None => return Ok(None),
+    };
+    match get(latest_info.target_address, GetOptions::default())? {
+        Some(element) => Ok(element
+            .into_inner()
+            .1
+            .to_app_option::<CategoryInfo>()?
+            .map(|info| (header_hash, info))),
+        None => Ok(None),
+    }
+}
+
+/// The category links of each category by the category's owner, oldest first.
+/// The owner is whoever linked the category's first info. Validation only lets the author
+/// of an info replace it, but anyone can link a first info of their own, which is ignored here.
+fn owner_category_links(links: LinkDetails) -> HashMap<EntryHash, Vec<(HeaderHash, CreateLink)>> {
+    let mut by_category: HashMap<EntryHash, Vec<(HeaderHash, CreateLink)>> = HashMap::new();
+    for (create, _) in links.into_inner() {
+        let create_link = match create.header() {
+            Header::CreateLink(create_link) => create_link,
+            _ => continue,
+        };
+        if let Some(category_hash) = CategoryTag::category_hash(&create_link.tag) {
+            by_category
+                .entry(category_hash)
+                .or_default()
+                .push((create.header_address().clone(), create_link.clone()));
+        }
+    }
+    for links in by_category.values_mut() {
+        links.sort_by_key(|(_, link)| link.timestamp);
+        if let Some(owner) = links.first().map(|(_, link)| link.author.clone()) {
+            links.retain(|(_, link)| link.author == owner);
+        }
+    }
+    by_category
+}
+
+/// Every registered category in sidebar order
+pub(crate) fn list_categories() -> ChatResult<CategoryList> {
+    let links = get_link_details(
+        categories_path().path_entry_hash()?,
+        Some(CategoryTag::any()),
+    )?;
+
+    // The latest info of each category
+    let get_input = owner_category_links(links)
+        .into_values()
+        .filter_map(|mut links| links.pop())
+        .map(|(_, link)| GetInput::new(link.target_address.into(), GetOptions::default()))
+        .collect();
+    let elements = HDK.with(|hdk| hdk.borrow().get(get_input))?;
+    let mut categories: Vec<CategoryInfo> = elements
+        .into_iter()
+        .flatten()
+        .filter_map(
+            |element| match element.into_inner().1.to_app_option::<CategoryInfo>() {
+                Ok(info) => info,
+                // Skip anything on the category links that isn't a category info
+                Err(e) => {
+                    debug!("Ignoring malformed category info: {:?}", e);
+                    None
+                }
+            },
+        )
+        .collect();
+    categories.sort_by(|a, b| {
+        (a.position, a.name.to_lowercase(), &a.category).cmp(&(
+            b.position,
+            b.name.to_lowercase(),
+            &b.category,
+        ))
+    });
+    Ok(categories.into())
+}
@@ -1,4 +1,9 @@
-use crate::timestamp::Timestamp;
+use crate::{
+    error::{ChatError, ChatResult},
+    message::MessagePreview,
+    roles::ChannelRoles,
+    timestamp::Timestamp,
+};
 use hdk::{hash_path::path::Component, prelude::*};
 use uuid::Uuid;
 pub mod handlers;
@@ -15,6 +20,18 @@ pub struct ChannelInfo {
     pub name: String,
     pub created_by: AgentPubKey,
     pub created_at: Timestamp,
+    /// Archived channels are left out of `list_channels` unless asked for
+    #[serde(default)]
+    pub archived: bool,
+    /// The least time in seconds between an agent's messages on the channel,
+    /// overriding the `slow_mode_seconds` in the DNA properties. 0 turns slow mode off.
+    #[serde(default)]
+    pub slow_mode_seconds: Option<u32>,
+    /// The channel info link this info replaces, `None` for the channel's first info.
+    /// Validation only accepts a replacement from the author of the info it replaces,
+    /// so every info in the chain is by whoever created the channel.
+    #[serde(default)]
+    pub previous: Option<HeaderHash>,
 }
 
 /// Input to the create channel call
@@ -24,14 +41,62 @@ pub struct ChannelInput {
     pub entry: Channel,
 }
 
+/// Input to the rename channel call
+#[derive(Debug, Serialize, Deserialize, SerializedBytes)]
+pub struct RenameChannelInput {
+    pub channel: Channel,
+    pub name: String,
+}
+
+/// Input to the set slow mode call
+#[derive(Debug, Serialize, Deserialize, SerializedBytes)]
+pub struct SlowModeInput {
+    pub channel: Channel,
+    /// `None` goes back to the DNA's slow mode
+    pub seconds: Option<u32>,
+}
+
 /// A channel is consists of the category it belongs to
 /// and a unique id
-#[derive(Debug, Clone, Serialize, Deserialize, SerializedBytes, PartialEq, Eq)]
+#[derive(
+    Debug, Clone, Serialize, Deserialize, SerializedBytes, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
 pub struct Channel {
     pub category: String,
     pub uuid: String,
 }
 
+impl Channel {
+    /// How many bytes at the start of a channel's uuid are taken from its owner's key
+    pub const OWNER_MARK_LENGTH: usize = 10;
+
+    /// A uuid for a new channel owned by `owner`:
+    /// the start of their key followed by as many bytes of `random` as fit
+    pub fn owned_uuid(owner: &AgentPubKey, random: &[u8]) -> String {
+        let mut bytes = [0; 16];
+        bytes[..Self::OWNER_MARK_LENGTH]
+            .copy_from_slice(&owner.get_raw_32()[..Self::OWNER_MARK_LENGTH]);
+        for (byte, random) in bytes[Self::OWNER_MARK_LENGTH..].iter_mut().zip(random) {
+            *byte = *random;
+        }
+        Uuid::from_bytes(bytes).to_string()
+    }
+
+    /// Whether the channel's uuid names `agent` as its owner.
+    /// Only the owner can link the channel's first info, so nobody else can claim it.
+    pub fn is_owned_by(&self, agent: &AgentPubKey) -> bool {
+        is_owner_uuid(&self.uuid, agent)
+    }
+}
+
+/// Whether a channel uuid starts with the owner mark of `agent`
+pub(crate) fn is_owner_uuid(uuid: &str, agent: &AgentPubKey) -> bool {
+    Uuid::parse_str(uuid).map_or(false, |uuid| {
+        uuid.as_bytes()[..Channel::OWNER_MARK_LENGTH]
+            == agent.get_raw_32()[..Channel::OWNER_MARK_LENGTH]
+    })
+}
+
 /*  using global chatters list for now.
 impl Channel {
     pub fn chatters_path(&self) -> Path {
@@ -43,50 +108,117 @@ impl Channel {
  */
 
 /// The message type that goes to the UI
-#[derive(
-    Serialize, Deserialize, SerializedBytes, derive_more::Constructor, Debug, Clone, PartialEq, Eq,
-)]
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct ChannelData {
     pub entry: Channel,
     pub info: ChannelInfo,
+    /// The newest message, only filled in by list channels when asked for
+    #[serde(default)]
+    pub last_message: Option<MessagePreview>,
+    /// Who can moderate the channel, only filled in by list channels when asked for
+    #[serde(default)]
+    pub roles: Option<ChannelRoles>,
 }
 
-/// Input to the list channels call
-#[derive(Debug, Serialize, Deserialize, SerializedBytes)]
+impl ChannelData {
+    pub fn new(entry: Channel, info: ChannelInfo) -> Self {
+        Self {
+            entry,
+            info,
+            last_message: None,
+            roles: None,
+        }
+    }
+}
+
+/// How list channels orders the channels
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ChannelSort {
+    /// By name, ignoring case
+    Name,
+    /// By when the channel was first created
+    CreatedAt,
+    /// By the latest of the channel's newest message and when it was last created, renamed or archived
+    LastActivity,
+}
+
+/// Input to the list channels call.
+/// Everything is optional, so `{ category }` lists every channel in a category
+/// and `{}` lists every channel in every registered category.
+#[derive(Debug, Serialize, Deserialize, SerializedBytes, Default, Clone)]
 pub struct ChannelListInput {
-    pub category: String,
+    /// Defaults to every registered category
+    pub category: Option<String>,
+    pub include_archived: Option<bool>,
+    /// Defaults to the order the channels were found in, or by uuid when a page is asked for
+    pub sort: Option<ChannelSort>,
+    pub descending: Option<bool>,
+    /// Only channels whose name starts with this, ignoring case
+    pub name_prefix: Option<String>,
+    /// Only channels created by this agent
+    pub created_by: Option<AgentPubKey>,
+    /// The most channels to return
+    pub limit: Option<usize>,
+    /// The `next_cursor` of the previous page
+    pub cursor: Option<String>,
+    /// Fill in a preview of each listed channel's newest message
+    pub include_preview: Option<bool>,
+    /// Fill in who can moderate each listed channel
+    pub include_roles: Option<bool>,
 }
 
 /// The channels returned from list channels
-#[derive(Debug, Serialize, Deserialize, SerializedBytes, derive_more::From)]
+#[derive(Debug, Serialize, Deserialize, SerializedBytes)]
 pub struct ChannelList {
     pub channels: Vec<ChannelData>,
+    /// Pass this as `cursor` to get the next page, `None` on the last page
+    #[serde(default)]
+    pub next_cursor: Option<String>,
+}
+
+impl From<Vec<ChannelData>> for ChannelList {
+    fn from(channels: Vec<ChannelData>) -> Self {
+        Self {
+            channels,
+            next_cursor: None,
+        }
+    }
 }
 
-impl From<Channel> for Path {
-    fn from(c: Channel) -> Self {
-        let u = Uuid::parse_str(&c.uuid).unwrap();
+impl TryFrom<Channel> for Path {
+    type Error = ChatError;
+
+    fn try_from(c: Channel) -> Result<Self, Self::Error> {
+        let u = Uuid::parse_str(&c.uuid).map_err(|_| ChatError::InvalidChannelUuid(c.uuid))?;
         let path = vec![
             Component::from(c.category.as_bytes().to_vec()),
             Component::from(u.to_u128_le().to_le_bytes().to_vec()),
         ];
-        Path::from(path)
+        Ok(Path::from(path))
     }
 }
 
 impl TryFrom<&Path> for Channel {
-    type Error = SerializedBytesError;
+    type Error = ChatError;
 
     fn try_from(p: &Path) -> Result<Self, Self::Error> {
         let path: &Vec<_> = p.as_ref();
-        let u128 = u128::from_le_bytes(path[1].as_ref().try_into().expect("wrong length"));
+        let (category, uuid) = match path.as_slice() {
+            [category, uuid, ..] => (category, uuid),
+            _ => return Err(ChatError::InvalidChannelPath),
+        };
+        let u128 = u128::from_le_bytes(
+            uuid.as_ref()
+                .try_into()
+                .map_err(|_| ChatError::InvalidChannelPath)?,
+        );
         let u = Uuid::from_u128(u128);
-        let c: String = std::str::from_utf8(path[0].as_ref())
-            .expect("bad string")
+        let c: String = std::str::from_utf8(category.as_ref())
+            .map_err(|_| ChatError::InvalidChannelPath)?
             .to_string();
         let channel = Channel {
-            category: String::try_from(c)?,
+            category: c,
             uuid: u.to_string(),
         };
         Ok(channel)
@@ -104,3 +236,58 @@ impl ChannelInfoTag {
         LinkTag::new(*Self::TAG)
     }
 }
+
+/// What a channel index link records about the channel info it points to,
+/// so channels can be filtered and sorted without getting every info
+#[derive(Debug, Clone, Serialize, Deserialize, SerializedBytes, PartialEq, Eq)]
+pub(crate) struct ChannelSummary {
+    pub(crate) uuid: String,
+    pub(crate) name: String,
+    pub(crate) created_by: AgentPubKey,
+    pub(crate) archived: bool,
+}
+
+impl From<&ChannelInfo> for ChannelSummary {
+    fn from(info: &ChannelInfo) -> Self {
+        Self {
+            uuid: info.uuid.clone(),
+            name: info.name.clone(),
+            created_by: info.created_by.clone(),
+            archived: info.archived,
+        }
+    }
+}
+
+/// Tags the links from a category to the latest info of each of its channels.
+/// The tag holds the channel's path hash followed by a summary of the info,
+/// so the latest info of every channel can be picked out of a single `get_links` on the category.
+pub(crate) struct ChannelIndexTag;
+
+impl ChannelIndexTag {
+    const TAG: &'static [u8; 5] = b"index";
+    const HASH_LENGTH: usize = 39;
+
+    /// Create the tag for this info of the channel with this path hash
+    pub(crate) fn tag(channel_hash: &EntryHash, info: &ChannelInfo) -> ChatResult<LinkTag> {
+        let summary: Vec<u8> =
+            UnsafeBytes::from(SerializedBytes::try_from(ChannelSummary::from(info))?).into();
+        Ok(LinkTag::new(
+            [&Self::TAG[..], channel_hash.get_raw_39(), &summary].concat(),
+        ))
+    }
+
+    /// The channel path hash and info summary in an index tag,
+    /// or `None` for any other tag or an index tag without a summary.
+    pub(crate) fn parse(tag: &LinkTag) -> Option<(EntryHash, ChannelSummary)> {
+        let bytes = tag.0.strip_prefix(&Self::TAG[..])?;
+        if bytes.len() <= Self::HASH_LENGTH {
+            return None;
+        }
+        let (hash, summary) = bytes.split_at(Self::HASH_LENGTH);
+        let channel_hash = EntryHash::from_raw_39(hash.to_vec()).ok()?;
+        let summary =
+            ChannelSummary::try_from(SerializedBytes::from(UnsafeBytes::from(summary.to_vec())))
+                .ok()?;
+        Some((channel_hash, summary))
+    }
+}
@@ -1,22 +1,98 @@
-use super::{ChannelData, ChannelInfo, ChannelInfoTag, ChannelList, ChannelListInput};
+use super::{
+    is_owner_uuid, ChannelData, ChannelIndexTag, ChannelInfo, ChannelInfoTag, ChannelList,
+    ChannelListInput, ChannelSort, ChannelSummary, RenameChannelInput, SlowModeInput,
+};
 use crate::{
+    batching_helper::get_newest_message_times,
+    category::handlers::{ensure_category, list_categories, DEFAULT_CATEGORY},
     channel::{Channel, ChannelInput},
-    error::ChatResult,
+    error::{ChatError, ChatResult},
+    message::handlers::{get_active_chatters, latest_message_previews},
+    moderation::moderation_from_links,
+    roles::{channels_links, role_state_from_links},
+    signals::{fan_out, SignalOptions},
+    SignalPayload,
 };
 use hdk::hash_path::path::Component;
 use hdk::prelude::*;
 use link::Link;
+use std::collections::HashMap;
 
 /// Create a new channel
 /// This effectively just stores channel info on the
-/// path that is `category:channel_id`
+/// path that is `category:channel_id`.
+/// The uuid must name us as the owner, see `new_channel_uuid`.
 pub(crate) fn create_channel(channel_input: ChannelInput) -> ChatResult<ChannelData> {
     let ChannelInput { name, entry } = channel_input;
+    if !entry.is_owned_by(&agent_info()?.agent_initial_pubkey) {
+        return Err(ChatError::NotChannelUuidOwner(entry.uuid));
+    }
 
     // Create the path for this channel
-    let path: Path = entry.clone().into();
+    let path: Path = entry.clone().try_into()?;
     path.ensure()?;
 
+    commit_channel_info(entry, name, false, None, None)
+}
+
+/// Rename a channel by linking a new channel info with the new name.
+/// Only the author of the current info may rename it.
+pub(crate) fn rename_channel(input: RenameChannelInput) -> ChatResult<ChannelData> {
+    let RenameChannelInput { channel, name } = input;
+    let (current_link, current) = get_latest_info(&channel)?;
+    check_channel_author(&current)?;
+    commit_channel_info(
+        channel,
+        name,
+        current.archived,
+        current.slow_mode_seconds,
+        Some(current_link),
+    )
+}
+
+/// Archive a channel by linking a new channel info that is marked as archived.
+/// Only the author of the current info may archive it.
+pub(crate) fn archive_channel(channel: Channel) -> ChatResult<ChannelData> {
+    let (current_link, current) = get_latest_info(&channel)?;
+    check_channel_author(&current)?;
+    commit_channel_info(
+        channel,
+        current.name,
+        true,
+        current.slow_mode_seconds,
+        Some(current_link),
+    )
+}
+
+/// Set or clear a channel's slow mode by linking a new channel info with it.
+/// Only the author of the current info may change it.
+pub(crate) fn set_slow_mode(input: SlowModeInput) -> ChatResult<ChannelData> {
+    let SlowModeInput { channel, seconds } = input;
+    let (current_link, current) = get_latest_info(&channel)?;
+    check_channel_author(&current)?;
+    commit_channel_info(
+        channel,
+        current.name,
+        current.archived,
+        seconds,
+        Some(current_link),
+    )
+}
+
+/// Commit a new channel info, link it to the channel and tell the active chatters.
+/// `previous` is the info link the new info replaces.
+fn commit_channel_info(
+    entry: Channel,
+    name: String,
+    archived: bool,
+    slow_mode_seconds: Option<u32>,
+    previous: Option<HeaderHash>,
+) -> ChatResult<ChannelData> {
+    let path: Path = entry.clone().try_into()?;
+    // So `list_channels` without a category finds it,
+    // including channels from before categories were registered once they change
+    ensure_category(&entry.category)?;
+
     // Create the channel info
     let info = ChannelInfo {
         category: entry.category.clone(),
@@ -26,6 +102,9 @@ pub(crate) fn create_channel(channel_input: ChannelInput) -> ChatResult<ChannelD
         // Right now
         created_at: sys_time()?,
         name,
+        archived,
+        slow_mode_seconds,
+        previous,
     };
 
     // Commit the channel info
@@ -33,94 +112,505 @@ pub(crate) fn create_channel(channel_input: ChannelInput) -> ChatResult<ChannelD
     let info_hash = hash_entry(&info)?;
 
     // link the channel info to the path
+    let channel_hash = path.path_entry_hash()?;
     create_link(
-        path.path_entry_hash()?,
-        info_hash,
+        channel_hash.clone(),
+        info_hash.clone(),
         HdkLinkType::Any,
         ChannelInfoTag::tag(),
     )?;
 
+    // and index it on the category so listing doesn't have to visit every channel
+    create_link(
+        category_path(&entry.category).path_entry_hash()?,
+        info_hash,
+        HdkLinkType::Any,
+        ChannelIndexTag::tag(&channel_hash, &info)?,
+    )?;
+
+    let channel_data = ChannelData::new(entry, info);
+    // The channel is committed either way, so a failed signal is only logged
+    if let Err(e) = signal_channel(channel_data.clone()) {
+        debug!("Failed to signal channel update: {:?}", e);
+    }
+
     // Return the channel and the info for the UI
-    Ok(ChannelData::new(entry, info))
+    Ok(channel_data)
+}
+
+/// Send a channel's latest data to every active chatter
+fn signal_channel(channel_data: ChannelData) -> ChatResult<()> {
+    let chatters = get_active_chatters()?.chatters;
+    let total = chatters.len();
+    fan_out(
+        SignalPayload::Channel(channel_data),
+        chatters,
+        total,
+        &SignalOptions::default(),
+    )?;
+    Ok(())
+}
+
+fn check_channel_author(info: &ChannelInfo) -> ChatResult<()> {
+    if info.created_by != agent_info()?.agent_initial_pubkey {
+        return Err(ChatError::NotChannelAuthor);
+    }
+    Ok(())
+}
+
+/// The latest channel info linked to a channel
+pub(crate) fn get_channel_info(channel: &Channel) -> ChatResult<ChannelInfo> {
+    Ok(get_latest_info(channel)?.1)
 }
 
-fn category_path(category: String) -> Path {
+/// The latest channel info linked to a channel by its owner, with the header of its link
+pub(crate) fn get_latest_info(channel: &Channel) -> ChatResult<(HeaderHash, ChannelInfo)> {
+    let (header_hash, latest_info) = owner_info_links(channel)?
+        .pop()
+        .ok_or_else(|| ChatError::MissingChannel(format!("{:?}", channel)))?;
+    let element = get(latest_info.target_address, GetOptions::default())?
+        .ok_or_else(|| ChatError::MissingChannel(format!("{:?}", channel)))?;
+    let info = element
+        .into_inner()
+        .1
+        .to_app_option::<ChannelInfo>()?
+        .ok_or(ChatError::DataFormatError(
+            "channel info link to something else",
+        ))?;
+    Ok((header_hash, info))
+}
+
+/// A uuid for a new channel owned by us
+pub(crate) fn new_channel_uuid() -> ChatResult<String> {
+    let random = random_bytes(16)?;
+    Ok(Channel::owned_uuid(
+        &agent_info()?.agent_initial_pubkey,
+        &random,
+    ))
+}
+
+/// The info links on a channel by its owner, oldest first.
+/// The owner is the agent the channel's uuid names. Validation only lets them link infos,
+/// so anything else here came from a misbehaving authority and is ignored.
+pub(crate) fn owner_info_links(channel: &Channel) -> ChatResult<Vec<(HeaderHash, CreateLink)>> {
+    let path: Path = channel.clone().try_into()?;
+    let mut links: Vec<(HeaderHash, CreateLink)> =
+        get_link_details(path.path_entry_hash()?, Some(ChannelInfoTag::tag()))?
+            .into_inner()
+            .into_iter()
+            .filter_map(|(create, _)| match create.header() {
+                Header::CreateLink(create_link) => {
+                    Some((create.header_address().clone(), create_link.clone()))
+                }
+                _ => None,
+            })
+            .collect();
+    links.retain(|(_, link)| channel.is_owned_by(&link.author));
+    links.sort_by_key(|(_, link)| link.timestamp);
+    Ok(links)
+}
+
+pub(crate) fn category_path(category: &str) -> Path {
     let path = vec![Component::from(category.as_bytes().to_vec())];
     Path::from(path)
 }
 
+/// An info linked to a channel by the channel's owner
+struct OwnedInfo {
+    info_hash: EntryHash,
+    timestamp: Timestamp,
+    summary: Option<ChannelSummary>,
+}
+
+/// The index links of one channel by the channel's owner, oldest first.
+/// Like `owner_info_links` the owner is the agent the uuid names, read from the summaries.
+/// `None` if there are none.
+fn owner_index_links(mut links: Vec<Link>) -> Option<Vec<OwnedInfo>> {
+    links.sort_by_key(|link| link.timestamp);
+    let infos: Vec<_> = links
+        .into_iter()
+        .filter_map(|link| {
+            let (_, summary) = ChannelIndexTag::parse(&link.tag)?;
+            if !is_owner_uuid(&summary.uuid, &summary.created_by) {
+                return None;
+            }
+            Some(OwnedInfo {
+                info_hash: link.target,
+                timestamp: link.timestamp,
+                summary: Some(summary),
+            })
+        })
+        .collect();
+    if infos.is_empty() {
+        None
+    } else {
+        Some(infos)
+    }
+}
+
+/// The infos linked on a channel's own path by the channel's owner, oldest first
+fn owner_infos(channel: &Channel) -> ChatResult<Vec<OwnedInfo>> {
+    Ok(owner_info_links(channel)?
+        .into_iter()
+        .map(|(_, link)| OwnedInfo {
+            info_hash: link.target_address,
+            timestamp: link.timestamp,
+            summary: None,
+        })
+        .collect())
+}
+
+/// A channel found on a category, before we know whether it's listed
+struct ListedChannel {
+    channel_hash: EntryHash,
+    info_hash: EntryHash,
+    /// When the channel's first info was linked
+    created_at: Timestamp,
+    /// When the channel's latest info was linked,
+    /// or its newest message if that is later and the channels are sorted by activity
+    last_activity: Timestamp,
+    summary: ChannelSummary,
+    /// Only fetched up front for channels that weren't indexed with a summary
+    info: Option<ChannelInfo>,
+}
+
+/// Where the sorted channels are compared
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+enum SortKey {
+    Position(usize),
+    Name(String),
+    Time(i64),
+}
+
+/// The last channel of a page, handed out as an opaque string
+#[derive(Debug, Serialize, Deserialize)]
+struct ChannelCursor {
+    key: SortKey,
+    uuid: String,
+}
+
+/// List channels, with a preview of each listed channel's newest message and its roles if asked for.
+/// The links those are read from are got for the whole page in one batch.
 pub(crate) fn list_channels(list_channels_input: ChannelListInput) -> ChatResult<ChannelList> {
-    // Get the category path
-    let path = category_path(list_channels_input.category);
-    // Get any channels on this path
-    let links = path.children()?;
-    let mut channels = Vec::with_capacity(links.len());
-
-    let mut channel_data: Vec<EntryHash> = Vec::new();
-    // For each channel get the channel info links and choose the latest
-    for target in links.into_iter().map(|link| link.target) {
-        // Path links have their full path as the tag so
-        // we don't need to get_links on the child.
-        // The tag can be turned into the channel path
-        // let channel_path = Path::try_from(&tag)?;
-
-        // // Turn the channel path into the channel
-        // let channel = Channel::try_from(&channel_path)?;
-
-        // Get any channel info links on this channel
-        let channel_info = get_links(target, Some(ChannelInfoTag::tag()))?;
-
-        // Find the latest
-        let latest_info = channel_info
-            .into_iter()
-            .fold(None, |latest: Option<Link>, link| match latest {
-                Some(latest) => {
-                    if link.timestamp > latest.timestamp {
-                        Some(link)
-                    } else {
-                        Some(latest)
+    let include_preview = list_channels_input.include_preview.unwrap_or(false);
+    let include_roles = list_channels_input.include_roles.unwrap_or(false);
+    let mut list = find_channels(list_channels_input)?;
+    if !include_preview && !include_roles {
+        return Ok(list);
+    }
+    let entries: Vec<Channel> = list.channels.iter().map(|c| c.entry.clone()).collect();
+    let mut moderations = Vec::new();
+    for (channel, links) in list.channels.iter_mut().zip(channels_links(&entries)?) {
+        let roles = role_state_from_links(&channel.entry, &links)?;
+        if include_preview {
+            moderations.push(moderation_from_links(links, &roles)?);
+        }
+        if include_roles {
+            channel.roles = Some(roles.roles);
+        }
+    }
+    if include_preview {
+        let previews = latest_message_previews(entries.into_iter().zip(moderations).collect())?;
+        for (channel, preview) in list.channels.iter_mut().zip(previews) {
+            channel.last_message = preview;
+        }
+    }
+    Ok(list)
+}
+
+/// List channels without looking at their messages
+pub(crate) fn find_channels(list_channels_input: ChannelListInput) -> ChatResult<ChannelList> {
+    let ChannelListInput {
+        category,
+        include_archived,
+        sort,
+        descending,
+        name_prefix,
+        created_by,
+        limit,
+        cursor,
+        ..
+    } = list_channels_input;
+    let include_archived = include_archived.unwrap_or(false);
+    let descending = descending.unwrap_or(false);
+    // Everything on the listed categories: the channel paths,
+    // and the indexed infos of channels created or updated since the index was added
+    let categories = listed_categories(category)?;
+    let links_input = categories
+        .iter()
+        .map(|category| {
+            Ok(GetLinksInput::new(
+                category_path(category).path_entry_hash()?,
+                None,
+            ))
+        })
+        .collect::<ChatResult<Vec<_>>>()?;
+    let links: Vec<Vec<Link>> = if links_input.is_empty() {
+        Vec::new()
+    } else {
+        HDK.with(|hdk| hdk.borrow().get_links(links_input))?
+    };
+
+    // Anyone can link anything on a category, so only index links
+    // and the links to channel paths count, everything else is skipped
+    let mut indexed: HashMap<EntryHash, Vec<Link>> = HashMap::new();
+    let mut channel_hashes = Vec::new();
+    for (category, links) in categories.iter().zip(links) {
+        for link in links {
+            match ChannelIndexTag::parse(&link.tag) {
+                Some((channel_hash, _)) => indexed.entry(channel_hash).or_default().push(link),
+                None => {
+                    if let Some(channel) = channel_path_link(category, &link)? {
+                        channel_hashes.push((link.target, channel));
                     }
                 }
-                None => Some(link),
-            });
+            }
+        }
+    }
 
-        // If there is none we will skip this channel
-        let latest_info = match latest_info {
-            Some(l) => l,
+    let mut listed = Vec::with_capacity(channel_hashes.len());
+    let mut unsummarised = Vec::new();
+    // For each channel choose the latest info
+    for (target, channel) in channel_hashes {
+        // Only the owner's infos count, so nobody else can rename or archive the channel
+        let mut infos = match indexed.remove(&target).and_then(owner_index_links) {
+            Some(infos) => infos,
+            // Channels nobody has touched since the index was added
+            // only have the channel info links on their own path
+            None => owner_infos(&channel)?,
+        };
+        let created_at = match infos.first() {
+            Some(first) => first.timestamp,
+            // If there is none we will skip this channel
+            None => continue,
+        };
+        let latest_info = match infos.pop() {
+            Some(latest) => latest,
             None => continue,
         };
+        match latest_info.summary {
+            Some(summary) => listed.push(ListedChannel {
+                channel_hash: target,
+                info_hash: latest_info.info_hash,
+                created_at,
+                last_activity: latest_info.timestamp,
+                summary,
+                info: None,
+            }),
+            None => unsummarised.push((
+                target,
+                latest_info.info_hash,
+                created_at,
+                latest_info.timestamp,
+            )),
+        }
+    }
+
+    // Without a summary we need the info itself to filter and sort
+    let infos = get_channel_infos(unsummarised.iter().map(|(_, hash, _, _)| hash.clone()))?;
+    for ((channel_hash, info_hash, created_at, last_activity), info) in
+        unsummarised.into_iter().zip(infos)
+    {
+        if let Some(info) = info {
+            listed.push(ListedChannel {
+                channel_hash,
+                info_hash,
+                created_at,
+                last_activity,
+                summary: ChannelSummary::from(&info),
+                info: Some(info),
+            });
+        }
+    }
 
-        channel_data.push(latest_info.target);
+    let name_prefix = name_prefix.map(|prefix| prefix.to_lowercase());
+    listed.retain(|channel| {
+        let summary = &channel.summary;
+        (include_archived || !summary.archived)
+            && name_prefix.as_ref().map_or(true, |prefix| {
+                summary.name.to_lowercase().starts_with(prefix)
+            })
+            && created_by
+                .as_ref()
+                .map_or(true, |agent| summary.created_by == *agent)
+    });
+
+    // A new message is activity too
+    if sort == Some(ChannelSort::LastActivity) {
+        let message_times = get_newest_message_times(
+            listed
+                .iter()
+                .map(|channel| channel.channel_hash.clone())
+                .collect(),
+        )?;
+        for (channel, message_time) in listed.iter_mut().zip(message_times) {
+            if let Some(message_time) = message_time {
+                channel.last_activity = channel.last_activity.max(message_time);
+            }
+        }
     }
-    let chan_results_input = channel_data
+
+    // Pages need an order that is the same on every call, so without a sort the uuid decides
+    let paged = limit.is_some() || cursor.is_some();
+
+    let mut sorted: Vec<(SortKey, String, ListedChannel)> = listed
         .into_iter()
+        .enumerate()
+        .map(|(position, channel)| {
+            let key = match sort {
+                None if paged => SortKey::Position(0),
+                None => SortKey::Position(position),
+                Some(ChannelSort::Name) => SortKey::Name(channel.summary.name.to_lowercase()),
+                Some(ChannelSort::CreatedAt) => SortKey::Time(channel.created_at.as_micros()),
+                Some(ChannelSort::LastActivity) => SortKey::Time(channel.last_activity.as_micros()),
+            };
+            (key, channel.summary.uuid.clone(), channel)
+        })
+        .collect();
+    // The uuid breaks ties so every channel has its own place for the cursor
+    sorted.sort_by(|a, b| (&a.0, &a.1).cmp(&(&b.0, &b.1)));
+    if descending {
+        sorted.reverse();
+    }
+
+    if let Some(cursor) = cursor {
+        let cursor: ChannelCursor =
+            serde_json::from_str(&cursor).map_err(|_| ChatError::InvalidCursor)?;
+        let after = (cursor.key, cursor.uuid);
+        sorted.retain(|(key, uuid, _)| {
+            let place = (key.clone(), uuid.clone());
+            if descending {
+                place < after
+            } else {
+                place > after
+            }
+        });
+    }
+
+    let next_cursor = match limit {
+        Some(limit) if sorted.len() > limit.max(1) => {
+            sorted.truncate(limit.max(1));
+            let last = sorted.last().map(|(key, uuid, _)| ChannelCursor {
+                key: key.clone(),
+                uuid: uuid.clone(),
+            });
+            last.map(|cursor| serde_json::to_string(&cursor))
+                .transpose()
+                .map_err(|_| ChatError::Generic("Failed to encode the channel list cursor"))?
+        }
+        _ => None,
+    };
+
+    // Only now get the infos of the channels on this page
+    let page: Vec<ListedChannel> = sorted.into_iter().map(|(_, _, channel)| channel).collect();
+    let mut fetched = get_channel_infos(
+        page.iter()
+            .filter(|channel| channel.info.is_none())
+            .map(|channel| channel.info_hash.clone()),
+    )?
+    .into_iter();
+    let mut channels = Vec::with_capacity(page.len());
+    for channel in page {
+        let info = match channel.info {
+            Some(info) => Some(info),
+            None => fetched.next().flatten(),
+        };
+        if let Some(info) = info {
+            // Turn the info into Channel
+            channels.push(ChannelData::new(
+                Channel {
+                    category: info.category.clone(),
+                    uuid: info.uuid.clone(),
+                },
+                info,
+            ))
+        }
+    }
+
+    // Return the channels data to the UI
+    Ok(ChannelList {
+        channels,
+        next_cursor,
+    })
+}
+
+/// Get channel infos in one batch, in the order of their hashes.
+/// Anything missing or not a channel info is `None`.
+fn get_channel_infos(
+    info_hashes: impl Iterator<Item = EntryHash>,
+) -> ChatResult<Vec<Option<ChannelInfo>>> {
+    let chan_results_input: Vec<GetInput> = info_hashes
         .map(|t| GetInput::new(t.into(), GetOptions::default()))
         .collect();
+    if chan_results_input.is_empty() {
+        return Ok(Vec::new());
+    }
     let all_channel_results_elements = HDK.with(|hdk| hdk.borrow().get(chan_results_input))?;
     // Get the actual channel info entry
-    for ele in all_channel_results_elements.into_iter() {
-        if let Some(element) = ele {
-            if let Some(info) = element.into_inner().1.to_app_option::<ChannelInfo>()? {
-                // Turn the info into Channel
-                channels.push(ChannelData {
-                    entry: Channel {
-                        category: info.category.clone(),
-                        uuid: info.uuid.clone(),
-                    },
-                    info,
-                })
+    Ok(all_channel_results_elements
+        .into_iter()
+        .map(|ele| {
+            let element = ele?;
+            match element.into_inner().1.to_app_option::<ChannelInfo>() {
+                Ok(info) => info,
+                // Skip anything on the channel info links that isn't a channel info
+                Err(e) => {
+                    debug!("Ignoring malformed channel info: {:?}", e);
+                    None
+                }
             }
-        }
+        })
+        .collect())
+}
+
+/// The channel a link on a category is to, if it is the link ensuring the channel's path puts there,
+/// with the channel's uuid in the tag and the channel's path as the target
+fn channel_path_link(category: &str, link: &Link) -> ChatResult<Option<Channel>> {
+    let bytes = SerializedBytes::from(UnsafeBytes::from(link.tag.0.clone()));
+    let component = match Component::try_from(bytes) {
+        Ok(component) => component,
+        Err(_) => return Ok(None),
+    };
+    let path = Path::from(vec![
+        Component::from(category.as_bytes().to_vec()),
+        component,
+    ]);
+    let channel = match Channel::try_from(&path) {
+        Ok(channel) => channel,
+        Err(_) => return Ok(None),
+    };
+    if path.path_entry_hash()? != link.target {
+        return Ok(None);
     }
+    Ok(Some(channel))
+}
 
-    // Return all the channels data to the UI
-    Ok(channels.into())
+/// The category asked for, or every registered category and the default one
+fn listed_categories(category: Option<String>) -> ChatResult<Vec<String>> {
+    Ok(match category {
+        Some(category) => vec![category],
+        None => {
+            let mut categories: Vec<String> = list_categories()?
+                .categories
+                .into_iter()
+                .map(|info| info.category)
+                .collect();
+            if !categories
+                .iter()
+                .any(|category| category == DEFAULT_CATEGORY)
+            {
+                categories.push(DEFAULT_CATEGORY.to_string());
+            }
+            categories
+        }
+    })
 }
 
 // Note: This function can get very heavy
 pub(crate) fn channel_stats(list_channels_input: ChannelListInput) -> ChatResult<(usize, usize)> {
-    let channel_path = category_path(list_channels_input.category);
-
-    let channel_links = channel_path.children()?;
-    Ok((channel_links.len(), 0))
+    let mut channels = 0;
+    for category in listed_categories(list_channels_input.category)? {
+        channels += category_path(&category).children()?.len();
+    }
+    Ok((channels, 0))
 }
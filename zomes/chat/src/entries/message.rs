@@ -1,4 +1,4 @@
-use crate::{error::ChatError, error::ChatResult, timestamp::Timestamp};
+use crate::{error::ChatError, error::ChatResult, signals::SignalOptions, timestamp::Timestamp};
 use hdk::prelude::*;
 
 use super::channel::{Channel, ChannelData};
@@ -27,6 +27,8 @@ pub struct MessageInput {
     pub last_seen: LastSeen,
     pub channel: Channel,
     pub entry: Message,
+    /// Signal the new message to the active chatters as part of the same call
+    pub signal: Option<bool>,
 }
 
 /// The message type that goes to the UI
@@ -37,6 +39,54 @@ pub struct MessageData {
     pub entry_hash: EntryHash,
     pub created_by: AgentPubKey,
     pub created_at: Timestamp,
+    /// A moderator hid this message, or its author was banned or muted when they sent it.
+    /// Only moderators who ask for hidden messages see these.
+    #[serde(default)]
+    pub hidden: bool,
+}
+
+/// Longest preview (in characters) of a channel's last message
+pub const MESSAGE_PREVIEW_LENGTH: usize = 100;
+
+/// A short look at a message, for showing next to its channel
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, SerializedBytes)]
+#[serde(rename_all = "camelCase")]
+pub struct MessagePreview {
+    pub entry_hash: EntryHash,
+    pub created_by: AgentPubKey,
+    pub created_at: Timestamp,
+    /// The start of the content, cut at `MESSAGE_PREVIEW_LENGTH` characters
+    pub content: String,
+    pub truncated: bool,
+}
+
+impl From<MessageData> for MessagePreview {
+    fn from(message: MessageData) -> Self {
+        let truncated = message.entry.content.chars().count() > MESSAGE_PREVIEW_LENGTH;
+        Self {
+            entry_hash: message.entry_hash,
+            created_by: message.created_by,
+            created_at: message.created_at,
+            content: message
+                .entry
+                .content
+                .chars()
+                .take(MESSAGE_PREVIEW_LENGTH)
+                .collect(),
+            truncated,
+        }
+    }
+}
+
+/// The message returned from create message.
+/// The message data is flattened so this reads the same as a `MessageData`,
+/// with the signal results added when the message was signalled.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, SerializedBytes)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatedMessage {
+    #[serde(flatten)]
+    pub message: MessageData,
+    pub sig_results: Option<SigResults>,
 }
 
 // Input to the signal_specific_chatters call
@@ -53,6 +103,9 @@ pub struct SignalSpecificInput {
 pub struct SignalMessageData {
     pub message_data: MessageData,
     pub channel_data: ChannelData,
+    /// The sender's signature over the message and channel data, added when the signal is sent
+    #[serde(default)]
+    pub signature: Option<Signature>,
 }
 /// Input to the list messages call
 #[derive(Debug, Clone, Serialize, Deserialize, SerializedBytes)]
@@ -61,12 +114,35 @@ pub struct ListMessagesInput {
     pub earliest_seen: Option<Timestamp>,
     // Keep expanding search interval until this count is reached
     pub target_message_count: usize, // UI will say 20 to start
+    /// Include messages hidden by moderators, marked as hidden.
+    /// Only moderators of the channel may ask for this.
+    #[serde(default)]
+    pub include_hidden: Option<bool>,
 }
 
+/// Input to the signal_chatters_with_options call
 #[derive(Debug, Serialize, Deserialize, SerializedBytes)]
+pub struct SignalChattersInput {
+    pub signal_message_data: SignalMessageData,
+    pub options: SignalOptions,
+}
+
+#[derive(Debug, Serialize, Deserialize, SerializedBytes, Clone, PartialEq)]
 pub struct SigResults {
+    /// Every chatter on the chatters path, active or not
     pub total: usize,
-    pub sent: Vec<String>,
+    /// How many agents the signal was sent to
+    pub sent_count: usize,
+    /// The agents the signal was sent to, only listed when asked for
+    pub sent: Option<Vec<String>>,
+}
+
+/// When we last linked ourselves on the chatters path, saved privately on the source chain
+/// so refreshing our presence doesn't have to search through all our links.
+#[hdk_entry(id = "chatter_refresh", visibility = "private")]
+#[derive(Clone, PartialEq, Eq)]
+pub struct ChatterRefresh {
+    pub refreshed_at: Timestamp,
 }
 
 #[derive(Debug, Serialize, Deserialize, SerializedBytes)]
@@ -74,6 +150,17 @@ pub struct ActiveChatters {
     pub chatters: Vec<AgentPubKey>,
 }
 
+/// How the active chatters changed over a period of time
+#[derive(Debug, Serialize, Deserialize, SerializedBytes, Clone, Default, PartialEq)]
+pub struct PresenceChanges {
+    /// Every chatter active now
+    pub active: Vec<AgentPubKey>,
+    /// Chatters active now that weren't at the start of the period
+    pub joined: Vec<AgentPubKey>,
+    /// Chatters active at the start of the period that aren't now
+    pub left: Vec<AgentPubKey>,
+}
+
 /// The messages returned from list messages
 #[derive(Debug, Serialize, Deserialize, SerializedBytes, derive_more::From, Clone, PartialEq)]
 pub struct ListMessages {
@@ -91,6 +178,7 @@ impl MessageData {
             entry_hash,
             created_by: header.author().to_owned(),
             created_at: header.timestamp().to_owned(),
+            hidden: false,
         })
     }
 }
@@ -100,23 +188,37 @@ impl SignalMessageData {
         Self {
             message_data,
             channel_data,
+            signature: None,
         }
     }
 }
 
 /// This key allows us to sort the messages by who they reply to
-/// then by time
+/// then by time.
+/// It also records the channel so validators can check the hour bucket the message is linked on,
+/// and the channel info link the author went by so they can read the channel's slow mode.
 #[derive(Debug, Clone, Serialize, Deserialize, SerializedBytes, Ord, PartialOrd, Eq, PartialEq)]
-struct LastSeenKey {
-    parent_hash: EntryHash,
-    timestamp: Option<Timestamp>,
+pub(crate) struct LastSeenKey {
+    pub(crate) parent_hash: EntryHash,
+    pub(crate) timestamp: Option<Timestamp>,
+    #[serde(default)]
+    pub(crate) channel: Option<Channel>,
+    #[serde(default)]
+    pub(crate) info: Option<HeaderHash>,
 }
 
 impl LastSeenKey {
-    pub fn new(parent_hash: EntryHash, timestamp: Timestamp) -> Self {
+    pub fn new(
+        parent_hash: EntryHash,
+        timestamp: Timestamp,
+        channel: Channel,
+        info: Option<HeaderHash>,
+    ) -> Self {
         Self {
             parent_hash,
             timestamp: Some(timestamp),
+            channel: Some(channel),
+            info,
         }
     }
 }
@@ -126,6 +228,8 @@ impl From<EntryHash> for LastSeenKey {
         Self {
             parent_hash,
             timestamp: None,
+            channel: None,
+            info: None,
         }
     }
 }
@@ -138,9 +242,11 @@ impl From<LastSeenKey> for LinkTag {
     }
 }
 
-impl From<LinkTag> for LastSeenKey {
-    fn from(t: LinkTag) -> Self {
+impl TryFrom<LinkTag> for LastSeenKey {
+    type Error = ChatError;
+
+    fn try_from(t: LinkTag) -> Result<Self, Self::Error> {
         Self::try_from(SerializedBytes::from(UnsafeBytes::from(t.0)))
-            .expect("This serialization should never fail")
+            .map_err(|_| ChatError::InvalidLinkTag)
     }
 }
@@ -1,9 +1,17 @@
 use crate::{
-    channel::Channel,
+    blocking::get_block_list,
+    channel::{
+        handlers::{get_channel_info, get_latest_info},
+        Channel, ChannelData, ChannelInfo,
+    },
     error::ChatError,
     error::ChatResult,
-    message::{Message, MessageInput},
-    utils::{get_local_header, to_date},
+    message::{CreatedMessage, Message, MessageInput, MessagePreview},
+    moderation::{get_channel_moderation, is_moderator, ChannelModeration},
+    outbox,
+    rate_limit::{check_rate_limit, RateLimitConfig},
+    signals::{fan_out, SignalOptions},
+    utils::{get_local_header, recent_elements, to_date},
     SignalPayload,
 };
 use hdk::prelude::*;
@@ -11,8 +19,8 @@ use link::Link;
 use metadata::EntryDetails;
 
 use super::{
-    ActiveChatters, LastSeen, LastSeenKey, ListMessages, ListMessagesInput, MessageData,
-    SigResults, SignalMessageData, SignalSpecificInput,
+    ActiveChatters, ChatterRefresh, LastSeen, LastSeenKey, ListMessages, ListMessagesInput,
+    MessageData, PresenceChanges, SigResults, SignalMessageData, SignalSpecificInput,
 };
 
 #[derive(Serialize, Deserialize, SerializedBytes, Debug)]
@@ -27,6 +35,29 @@ pub struct FakeMessage {
     pub timestamp: Timestamp,
 }
 
+#[derive(Serialize, Deserialize, SerializedBytes, Debug)]
+pub struct LinkFakeMessagePayload {
+    pub message: EntryHash,
+    pub channel: Channel,
+    pub timestamp: Timestamp,
+}
+
+/// Link a message that is already on the DHT into a channel without committing it again
+pub(crate) fn link_fake_message(input: LinkFakeMessagePayload) -> ChatResult<()> {
+    let LinkFakeMessagePayload {
+        message,
+        channel,
+        timestamp,
+    } = input;
+    let path: Path = channel.clone().try_into()?;
+    let path = crate::batching_helper::timestamp_into_path(path, timestamp)?;
+    path.ensure()?;
+    let path_hash = path.path_entry_hash()?;
+    let tag = LastSeenKey::new(path_hash.clone(), timestamp, channel, None);
+    create_link(path_hash, message, HdkLinkType::Any, LinkTag::from(tag))?;
+    Ok(())
+}
+
 /// Create a new message
 pub(crate) fn insert_fake_messages(input: InsertFakeMessagesPayload) -> ChatResult<()> {
     for FakeMessage { content, timestamp } in input.messages {
@@ -38,6 +69,7 @@ pub(crate) fn insert_fake_messages(input: InsertFakeMessagesPayload) -> ChatResu
                     uuid: "".into(),
                     content,
                 },
+                signal: None,
             },
             timestamp,
         )?;
@@ -46,11 +78,270 @@ pub(crate) fn insert_fake_messages(input: InsertFakeMessagesPayload) -> ChatResu
     Ok(())
 }
 
-/// Create a new message
+/// Create a new message and signal it to the active chatters if asked to
+pub(crate) fn create_and_signal_message(
+    message_input: MessageInput,
+    time: Timestamp,
+) -> ChatResult<CreatedMessage> {
+    let signal = message_input.signal.unwrap_or(false);
+    let channel = message_input.channel.clone();
+    // A retry of a message we already have was signalled, or left to the outbox, the first time
+    if let Some(message) = find_existing_message(&channel, &message_input.entry.uuid)? {
+        return Ok(CreatedMessage {
+            message,
+            sig_results: None,
+        });
+    }
+    check_not_silenced(&channel)?;
+    let info = current_info(&channel)?;
+    // Fail early with a clear error. Validation rejects the message link regardless.
+    let (chain_head, _, _) = agent_info()?.chain_head;
+    if let Some(error) = check_rate_limit(
+        &RateLimitConfig::load()?,
+        &channel,
+        info.as_ref().map(|(_, info)| info),
+        time,
+        chain_head,
+    )? {
+        return Err(error);
+    }
+    let (header_hash, message) = commit_message(message_input, time, info.map(|(link, _)| link))?;
+
+    let sig_results = if signal {
+        // The message is committed either way, so a failed signal is left to the outbox
+        match signal_new_message(message.clone(), channel.clone()) {
+            Ok(sig_results) => Some(sig_results),
+            Err(e) => {
+                debug!("Failed to signal new message: {:?}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+    outbox::record(
+        header_hash,
+        message.clone(),
+        channel,
+        signal && sig_results.is_none(),
+    )?;
+
+    Ok(CreatedMessage {
+        message,
+        sig_results,
+    })
+}
+
+pub(crate) fn signal_new_message(message: MessageData, channel: Channel) -> ChatResult<SigResults> {
+    let info = get_channel_info(&channel)?;
+    let channel_data = ChannelData::new(channel, info);
+    signal_chatters(
+        SignalMessageData::new(message, channel_data),
+        SignalOptions::default(),
+    )
+}
+
+/// Create a new message.
+/// If we already created a message with the same uuid on this channel that message is returned instead.
 pub(crate) fn create_message(
     message_input: MessageInput,
     time: Timestamp,
 ) -> ChatResult<MessageData> {
+    match find_existing_message(&message_input.channel, &message_input.entry.uuid)? {
+        Some(message) => Ok(message),
+        None => {
+            let channel = message_input.channel.clone();
+            let info = current_info(&channel)?;
+            let (header_hash, message) =
+                commit_message(message_input, time, info.map(|(link, _)| link))?;
+            outbox::record(header_hash, message.clone(), channel, false)?;
+            Ok(message)
+        }
+    }
+}
+
+/// The latest info on a channel with the header of its link, for messages to cite.
+/// A channel without an info yet has nothing to cite.
+fn current_info(channel: &Channel) -> ChatResult<Option<(HeaderHash, ChannelInfo)>> {
+    match get_latest_info(channel) {
+        Ok(info) => Ok(Some(info)),
+        Err(ChatError::MissingChannel(_)) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// How far back on our chain we look for a message being created again.
+/// Retries come soon after the original, so this is a couple of dozen messages.
+/// Every message in the window is read on each create, so a wider window makes
+/// creating messages slower on a long chain.
+pub const RETRY_WINDOW_HEADERS: u32 = 100;
+
+/// Find a recent message on our own chain with this uuid that was linked on `channel`.
+/// Messages without a uuid are never considered the same.
+fn find_existing_message(channel: &Channel, uuid: &str) -> ChatResult<Option<MessageData>> {
+    if uuid.is_empty() {
+        return Ok(None);
+    }
+    let uuids = std::iter::once(uuid.to_string()).collect();
+    Ok(my_messages_by_uuid(&uuids, RETRY_WINDOW_HEADERS)?
+        .remove(&(channel.clone(), uuid.to_string())))
+}
+
+/// Messages in the last `window` headers of our chain with one of these uuids,
+/// keyed by channel and uuid
+fn my_messages_by_uuid(
+    uuids: &HashSet<String>,
+    window: u32,
+) -> ChatResult<HashMap<(Channel, String), MessageData>> {
+    let mut found = HashMap::new();
+    if uuids.is_empty() {
+        return Ok(found);
+    }
+    for (channel, message, _) in my_recent_messages(window)? {
+        if uuids.contains(&message.entry.uuid) {
+            found
+                .entry((channel, message.entry.uuid.clone()))
+                .or_insert(message);
+        }
+    }
+    Ok(found)
+}
+
+/// The messages in the last `window` headers of our chain that we linked on a channel there,
+/// with the channel and the header seq of the link, in the order they were linked.
+/// Only the message entries and the link headers are read, not every entry in the window.
+pub(crate) fn my_recent_messages(window: u32) -> ChatResult<Vec<(Channel, MessageData, u32)>> {
+    let message_elements = recent_elements(
+        window,
+        QueryFilter::new()
+            .entry_type(entry_type!(Message)?)
+            .include_entries(true),
+    )?;
+    let mut messages = HashMap::new();
+    for element in message_elements.iter() {
+        if let Some(message) = element.entry().to_app_option::<Message>()? {
+            let header = element.header().clone();
+            if let Some(entry_hash) = header.entry_hash() {
+                messages.insert(entry_hash.clone(), (header, message));
+            }
+        }
+    }
+    if messages.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // The message entry doesn't know its channel, so look for our links to it
+    let link_elements = recent_elements(
+        window,
+        QueryFilter::new().header_type(HeaderType::CreateLink),
+    )?;
+    let mut linked = Vec::new();
+    for element in link_elements.iter() {
+        if let Header::CreateLink(create_link) = element.header() {
+            let channel = match LastSeenKey::try_from(create_link.tag.clone()) {
+                Ok(LastSeenKey {
+                    channel: Some(channel),
+                    ..
+                }) => channel,
+                _ => continue,
+            };
+            if let Some((header, message)) = messages.get(&create_link.target_address) {
+                linked.push((
+                    channel,
+                    MessageData::new(header.clone(), message.clone())?,
+                    element.header().header_seq(),
+                ));
+            }
+        }
+    }
+    linked.sort_by_key(|(_, _, seq)| *seq);
+    Ok(linked)
+}
+
+/// Commit a message and link it on its channel, citing the channel's info link.
+/// Returns the message's header hash with the message.
+fn commit_message(
+    message_input: MessageInput,
+    time: Timestamp,
+    info: Option<HeaderHash>,
+) -> ChatResult<(HeaderHash, MessageData)> {
+    let entry = message_input.entry.clone();
+    let header_hash = commit_and_link(message_input, time, info, &mut HashSet::new())?;
+
+    // Get the local header and create the message type for the UI
+    let header = get_local_header(&header_hash)?.ok_or(ChatError::MissingLocalHeader)?;
+    Ok((header_hash, MessageData::new(header, entry)?))
+}
+
+/// Create many messages in a single call, all in the bucket for `time`.
+/// Messages we already created are returned as they are, like `create_message` does.
+/// Each new message is checked against the rate limits as it would be on its own,
+/// and if any breaks them the whole batch fails with that error.
+/// Nothing is signalled, so the messages show up for others on their next list or sync.
+pub(crate) fn create_messages(
+    inputs: Vec<MessageInput>,
+    time: Timestamp,
+) -> ChatResult<Vec<MessageData>> {
+    let uuids = inputs
+        .iter()
+        .map(|input| input.entry.uuid.clone())
+        .filter(|uuid| !uuid.is_empty())
+        .collect();
+    // A retried batch is at most a few headers per message further back
+    let window = RETRY_WINDOW_HEADERS.saturating_add(5 * inputs.len() as u32);
+    let mut existing = my_messages_by_uuid(&uuids, window)?;
+    let channels: HashSet<_> = inputs.iter().map(|input| &input.channel).collect();
+    let mut infos = HashMap::new();
+    for channel in channels {
+        check_not_silenced(channel)?;
+        infos.insert(channel.clone(), current_info(channel)?);
+    }
+
+    let config = RateLimitConfig::load()?;
+    let mut ensured = HashSet::new();
+    inputs
+        .into_iter()
+        .map(|input| {
+            let key = (input.channel.clone(), input.entry.uuid.clone());
+            if let Some(message) = existing.get(&key) {
+                return Ok(message.clone());
+            }
+            let entry = input.entry.clone();
+            let channel = input.channel.clone();
+            let info = infos.get(&channel).cloned().flatten();
+            // Messages earlier in the batch are on our chain by now and count towards the limits
+            let (chain_head, _, _) = agent_info()?.chain_head;
+            if let Some(error) = check_rate_limit(
+                &config,
+                &channel,
+                info.as_ref().map(|(_, info)| info),
+                time,
+                chain_head,
+            )? {
+                return Err(error);
+            }
+            let info = info.map(|(link, _)| link);
+            let header_hash = commit_and_link(input, time, info, &mut ensured)?;
+            let header = get_local_header(&header_hash)?.ok_or(ChatError::MissingLocalHeader)?;
+            let message = MessageData::new(header, entry)?;
+            outbox::record(header_hash, message.clone(), channel, false)?;
+            // Repeats later in this batch get this message back
+            if !key.1.is_empty() {
+                existing.insert(key, message.clone());
+            }
+            Ok(message)
+        })
+        .collect()
+}
+
+/// Commit the message entry and link it on the bucket path for `time`, citing the channel info link `info`.
+/// Paths already in `ensured` aren't ensured again.
+fn commit_and_link(
+    message_input: MessageInput,
+    time: Timestamp,
+    info: Option<HeaderHash>,
+    ensured: &mut HashSet<EntryHash>,
+) -> ChatResult<HeaderHash> {
     let MessageInput {
         last_seen,
         channel,
@@ -60,22 +351,22 @@ pub(crate) fn create_message(
 
     // Commit the message
     let header_hash = create_entry(&entry)?;
-
-    // Get the local header and create the message type for the UI
-    let header = get_local_header(&header_hash)?.ok_or(ChatError::MissingLocalHeader)?;
-    let message = MessageData::new(header, entry)?;
+    let entry_hash = hash_entry(&entry)?;
 
     // Get the channel hash
-    let path: Path = channel.clone().into();
+    let path: Path = channel.clone().try_into()?;
 
     // Add the current time components
     let path = crate::batching_helper::timestamp_into_path(path, time)?;
 
-    // Ensure the path exists
-    path.ensure()?;
-
     // The actual hash we are going to hang this message on
     let path_hash = path.path_entry_hash()?;
+
+    // Ensure the path exists
+    if ensured.insert(path_hash.clone()) {
+        path.ensure()?;
+    }
+
     // debug!(
     //     "committing message to hour {:?}",
     //     crate::batching_helper::last_segment_from_path(&path)?
@@ -85,17 +376,11 @@ pub(crate) fn create_message(
         LastSeen::Message(hash_entry) => hash_entry,
         LastSeen::First => path_hash.clone(),
     };
-    // Turn the reply to and timestamp into a link tag
-    let tag = LastSeenKey::new(parent_hash_entry, message.created_at);
-    create_link(
-        path_hash,
-        message.entry_hash.clone(),
-        HdkLinkType::Any,
-        LinkTag::from(tag),
-    )?;
+    // Turn the reply to, bucket time and channel into a link tag
+    let tag = LastSeenKey::new(parent_hash_entry, time, channel, info);
+    create_link(path_hash, entry_hash, HdkLinkType::Any, LinkTag::from(tag))?;
 
-    // Return the message for the UI
-    Ok(message)
+    Ok(header_hash)
 }
 
 /// Using batching to List all the messages on this channel
@@ -104,12 +389,23 @@ pub(crate) fn list_messages(list_message_input: ListMessagesInput) -> ChatResult
         channel,
         earliest_seen,
         target_message_count,
+        include_hidden,
     } = list_message_input;
+    let include_hidden = include_hidden.unwrap_or(false);
+    if include_hidden && !is_moderator(&channel)? {
+        return Err(ChatError::NotAuthorized);
+    }
 
-    let path: Path = channel.into();
+    let moderation = get_channel_moderation(&channel)?;
+    let path: Path = channel.try_into()?;
     let links =
         crate::batching_helper::get_message_links(path, earliest_seen, target_message_count)?;
     let mut messages = get_messages(links)?;
+    for message in messages.iter_mut() {
+        message.hidden = moderation.is_removed(message);
+    }
+    let blocked = get_block_list()?;
+    messages.retain(|m| (include_hidden || !m.hidden) && !blocked.is_blocked(&m.created_by));
     debug!("Total length of messages {:?}", messages.len());
 
     // Return messages in timestamp-ascending order.
@@ -119,6 +415,75 @@ pub(crate) fn list_messages(list_message_input: ListMessagesInput) -> ChatResult
     Ok(messages.into())
 }
 
+/// List every message on this channel created after `since`
+pub(crate) fn list_messages_since(
+    channel: Channel,
+    since: Timestamp,
+) -> ChatResult<Vec<MessageData>> {
+    let moderation = get_channel_moderation(&channel)?;
+    let path: Path = channel.try_into()?;
+    let links = crate::batching_helper::get_message_links_since(path, since)?;
+    let mut messages = get_messages(links)?;
+    let blocked = get_block_list()?;
+    messages.retain(|m| {
+        m.created_at > since && !moderation.is_removed(m) && !blocked.is_blocked(&m.created_by)
+    });
+    messages.sort_unstable_by_key(|m| m.created_at);
+    Ok(messages)
+}
+
+/// A preview of the newest message on each channel that isn't hidden, in the order of the channels.
+/// Only the newest hour with messages is looked at, so if they are all hidden there is no preview.
+/// The newest message of every channel is got in one batch,
+/// and only channels whose newest message is hidden go on to get the rest one at a time.
+pub(crate) fn latest_message_previews(
+    channels: Vec<(Channel, ChannelModeration)>,
+) -> ChatResult<Vec<Option<MessagePreview>>> {
+    let blocked = get_block_list()?;
+    let mut candidates = Vec::with_capacity(channels.len());
+    for (channel, moderation) in channels.iter() {
+        let path: Path = channel.clone().try_into()?;
+        let mut links = crate::batching_helper::get_newest_message_links(path)?;
+        links.retain(|link| !moderation.is_hidden(&link.target));
+        links.sort_unstable_by_key(|link| std::cmp::Reverse(link.timestamp));
+        candidates.push(links);
+    }
+    let newest: HashMap<EntryHash, MessageData> = get_messages(
+        candidates
+            .iter()
+            .filter_map(|links| links.first().cloned())
+            .collect(),
+    )?
+    .into_iter()
+    .map(|message| (message.entry_hash.clone(), message))
+    .collect();
+    let shown = |message: &MessageData, moderation: &ChannelModeration| {
+        !moderation.is_removed(message) && !blocked.is_blocked(&message.created_by)
+    };
+
+    let mut previews = Vec::with_capacity(channels.len());
+    for ((_, moderation), links) in channels.iter().zip(candidates) {
+        let mut links = links.into_iter();
+        let mut preview = links
+            .next()
+            .and_then(|link| newest.get(&link.target))
+            .filter(|message| shown(message, moderation))
+            .map(|message| message.clone().into());
+        if preview.is_none() {
+            for link in links {
+                if let Some(message) = get_messages(vec![link])?.into_iter().next() {
+                    if shown(&message, moderation) {
+                        preview = Some(message.into());
+                        break;
+                    }
+                }
+            }
+        }
+        previews.push(preview);
+    }
+    Ok(previews)
+}
+
 // pub(crate) fn _new_message_signal(message: SignalMessageData) -> ChatResult<()> {
 //     debug!(
 //         "Received message: {:?}",
@@ -145,7 +510,14 @@ fn get_messages(links: Vec<Link>) -> ChatResult<Vec<MessageData>> {
                 entry, mut headers, ..
             })) => {
                 // Turn the entry into a MessageEntry
-                let message: Message = entry.try_into()?;
+                let message: Message = match entry.try_into() {
+                    Ok(message) => message,
+                    // Ignoring anything linked on the channel that isn't a message
+                    Err(e) => {
+                        debug!("Ignoring malformed message: {:?}", e);
+                        continue;
+                    }
+                };
                 let signed_header = match headers.pop() {
                     Some(h) => h,
                     // Ignoring missing messages
@@ -163,7 +535,18 @@ fn get_messages(links: Vec<Link>) -> ChatResult<Vec<MessageData>> {
             _ => continue, // Create the message type for the UI
         }
     }
-    Ok(messages)
+    Ok(dedupe_messages(messages))
+}
+
+/// Keep only the earliest message for each author and uuid.
+/// Retried creates used to commit the same message more than once.
+fn dedupe_messages(mut messages: Vec<MessageData>) -> Vec<MessageData> {
+    messages.sort_by_key(|m| m.created_at);
+    let mut seen = HashSet::new();
+    messages.retain(|m| {
+        m.entry.uuid.is_empty() || seen.insert((m.created_by.clone(), m.entry.uuid.clone()))
+    });
+    messages
 }
 
 pub fn chatters_path() -> Path {
@@ -191,7 +574,7 @@ pub(crate) fn signal_users_on_channel(signal_message_data: SignalMessageData) ->
 
 const CHATTER_REFRESH_HOURS: i64 = 2;
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 /// return the list of active chatters on a path.
 /// N.B.: assumes that the path has been ensured elsewhere.
@@ -230,14 +613,85 @@ fn active_chatters(chatters_path: Path) -> ChatResult<(usize, Vec<AgentPubKey>)>
     Ok((total, active))
 }
 
+/// Who became active or inactive between `since` and now
+pub(crate) fn presence_since(since: Timestamp) -> ChatResult<PresenceChanges> {
+    let me = agent_info()?.agent_latest_pubkey;
+    let chatters = get_links(chatters_path().path_entry_hash()?, None)?;
+    let now = to_date(sys_time()?);
+    let since = to_date(since);
+
+    // The latest refresh of every agent up to now and up to `since`
+    let mut latest_now: HashMap<AgentPubKey, chrono::DateTime<chrono::Utc>> = HashMap::new();
+    let mut latest_since: HashMap<AgentPubKey, chrono::DateTime<chrono::Utc>> = HashMap::new();
+    for link in chatters {
+        let agent = match tag_to_agent(link.tag) {
+            Ok(agent) => agent,
+            Err(_) => continue,
+        };
+        if agent == me {
+            continue;
+        }
+        let link_time = to_date(link.timestamp);
+        let latest = latest_now.entry(agent.clone()).or_insert(link_time);
+        *latest = (*latest).max(link_time);
+        if link_time <= since {
+            let latest = latest_since.entry(agent).or_insert(link_time);
+            *latest = (*latest).max(link_time);
+        }
+    }
+
+    let is_active = |at: chrono::DateTime<chrono::Utc>,
+                     refreshed: Option<&chrono::DateTime<chrono::Utc>>| {
+        refreshed
+            .map(|refreshed| {
+                at.signed_duration_since(*refreshed).num_hours() < CHATTER_REFRESH_HOURS
+            })
+            .unwrap_or(false)
+    };
+    let mut changes = PresenceChanges::default();
+    for (agent, refreshed) in latest_now.iter() {
+        let active_now = is_active(now, Some(refreshed));
+        let active_since = is_active(since, latest_since.get(agent));
+        if active_now {
+            changes.active.push(agent.clone());
+        }
+        match (active_since, active_now) {
+            (false, true) => changes.joined.push(agent.clone()),
+            (true, false) => changes.left.push(agent.clone()),
+            _ => (),
+        }
+    }
+    Ok(changes)
+}
+
 pub(crate) fn get_active_chatters() -> ChatResult<ActiveChatters> {
     let me = agent_info()?.agent_latest_pubkey;
     let chatters_path: Path = chatters_path();
     let (_total, mut chatters) = active_chatters(chatters_path)?;
-    chatters.retain(|a| *a != me);
+    let blocked = get_block_list()?;
+    chatters.retain(|a| *a != me && !blocked.is_blocked(a));
     Ok(ActiveChatters { chatters })
 }
 
+/// The active chatters who aren't banned or muted on this channel
+pub(crate) fn get_channel_chatters(channel: &Channel) -> ChatResult<ActiveChatters> {
+    let moderation = get_channel_moderation(channel)?;
+    let now = sys_time()?;
+    let mut chatters = get_active_chatters()?.chatters;
+    chatters.retain(|a| !moderation.is_silenced(a, now));
+    Ok(ActiveChatters { chatters })
+}
+
+/// Fail early if we can't post on this channel.
+/// Messages sent anyway are dropped when read, see `moderation`.
+fn check_not_silenced(channel: &Channel) -> ChatResult<()> {
+    let me = agent_info()?.agent_initial_pubkey;
+    if get_channel_moderation(channel)?.is_silenced(&me, sys_time()?) {
+        return Err(ChatError::Silenced);
+    }
+    Ok(())
+}
+
 pub(crate) fn signal_specific_chatters(input: SignalSpecificInput) -> ChatResult<()> {
     let mut chatters = input.chatters;
 
@@ -249,73 +703,65 @@ pub(crate) fn signal_specific_chatters(input: SignalSpecificInput) -> ChatResult
             }
         }
     }
+    let total = chatters.len();
     let input = SignalPayload::Message(input.signal_message_data);
-    let payload = ExternIO::encode(input)?;
-    remote_signal(payload, chatters)?;
+    fan_out(input, chatters, total, &SignalOptions::default())?;
     Ok(())
 }
 
-pub(crate) fn signal_chatters(signal_message_data: SignalMessageData) -> ChatResult<SigResults> {
+pub(crate) fn signal_chatters(
+    signal_message_data: SignalMessageData,
+    options: SignalOptions,
+) -> ChatResult<SigResults> {
     let me = agent_info()?.agent_latest_pubkey;
     let chatters_path: Path = chatters_path();
     let (total, mut active_chatters) = active_chatters(chatters_path)?;
-    active_chatters.retain(|a| *a != me);
-    debug!("sending to {:?}", active_chatters);
+    let moderation = get_channel_moderation(&signal_message_data.channel_data.entry)?;
+    let now = sys_time()?;
+    active_chatters.retain(|a| *a != me && !moderation.is_silenced(a, now));
 
-    let mut sent: Vec<String> = Vec::new();
-    for a in active_chatters.clone() {
-        sent.push(format!("{}", a.to_string()));
-    }
     let input = SignalPayload::Message(signal_message_data);
-    let payload = ExternIO::encode(input)?;
-    remote_signal(payload, active_chatters)?;
-    Ok(SigResults { total, sent })
+    fan_out(input, active_chatters, total, &options)
 }
 
-pub(crate) fn is_active_chatter(chatters_path: Path) -> ChatResult<bool> {
-    let base = chatters_path.path_entry_hash()?;
-    let filter = QueryFilter::new();
-    let header_filter = filter.header_type(HeaderType::CreateLink);
-    let query_result: Vec<Element> = query(header_filter)?;
+/// Whether our last refresh is recent enough that we still count as active
+pub(crate) fn is_active_chatter() -> ChatResult<bool> {
+    let filter = QueryFilter::new()
+        .entry_type(entry_type!(ChatterRefresh)?)
+        .include_entries(true);
+    let latest = query(filter)?
+        .into_iter()
+        .max_by_key(|element| element.header().header_seq());
+    let refreshed_at = match latest {
+        Some(element) => match element.entry().to_app_option::<ChatterRefresh>()? {
+            Some(refresh) => refresh.refreshed_at,
+            None => return Ok(false),
+        },
+        None => return Ok(false),
+    };
     let now = to_date(sys_time()?);
-    let mut pass = false;
-    for x in query_result {
-        match x.header() {
-            Header::CreateLink(c) => {
-                if c.base_address == base {
-                    let link_time = to_date(c.timestamp);
-                    if now.signed_duration_since(link_time).num_hours() < CHATTER_REFRESH_HOURS {
-                        pass = true;
-                        break;
-                    } else {
-                        pass = false;
-                        break;
-                    }
-                } else {
-                    continue;
-                }
-            }
-            _ => unreachable!(),
-        }
-    }
-    Ok(pass)
+    Ok(now.signed_duration_since(to_date(refreshed_at)).num_hours() < CHATTER_REFRESH_HOURS)
 }
 
 // TODO: re add chatter/channel instead of global
 // simplified and expected as a zome call
 pub(crate) fn refresh_chatter() -> ChatResult<()> {
+    if is_active_chatter()? {
+        return Ok(());
+    }
     let path: Path = chatters_path();
     path.ensure()?;
     let agent = agent_info()?.agent_latest_pubkey;
     let agent_tag = agent_to_tag(&agent);
-    if !is_active_chatter(path.clone())? {
-        create_link(
-            path.path_entry_hash()?,
-            agent.into(),
-            HdkLinkType::Any,
-            agent_tag.clone(),
-        )?;
-    }
+    create_link(
+        path.path_entry_hash()?,
+        agent.into(),
+        HdkLinkType::Any,
+        agent_tag,
+    )?;
+    create_entry(&ChatterRefresh {
+        refreshed_at: sys_time()?,
+    })?;
     Ok(())
 }
 
@@ -31,14 +31,187 @@ pub enum ChatError {
     ReadOnly,
     #[error("Expected batching-related path to contain more segments")]
     InvalidBatchingPath,
+    #[error("Channel uuid {0} is not a valid uuid")]
+    InvalidChannelUuid(String),
+    #[error("Path does not describe a channel")]
+    InvalidChannelPath,
+    #[error("Failed to decode a message link tag")]
+    InvalidLinkTag,
+    #[error("Only the author of a channel's info can change it")]
+    NotChannelAuthor,
+    #[error("Channel uuid {0} doesn't name you as the owner, get one from new_channel_uuid")]
+    NotChannelUuidOwner(String),
+    #[error("Category {0} doesn't exist")]
+    MissingCategory(String),
+    #[error("Category {0} already exists")]
+    CategoryExists(String),
+    #[error("Only the author of a category's info can change it")]
+    NotCategoryAuthor,
+    #[error("The channel list cursor is not one we handed out")]
+    InvalidCursor,
+    #[error("You don't have a role on this channel that allows this")]
+    NotAuthorized,
+    #[error("You are banned or muted on this channel")]
+    Silenced,
+    #[error("You already reported this message")]
+    AlreadyReported,
+    #[error("Too many messages, wait a minute before sending more")]
+    RateLimited,
+    #[error("This channel is in slow mode, wait before sending another message")]
+    SlowMode,
     #[error("Generic Error: {0}")]
     Generic(&'static str),
 }
 
 pub type ChatResult<T> = Result<T, ChatError>;
 
+/// Stable, machine-readable codes for every `ChatError`.
+///
+/// These are part of the zome API: clients should match on the code
+/// instead of the message, so a code is never renamed or reused.
+///
+/// | Code | Meaning |
+/// |------|---------|
+/// | `SERIALIZATION` | Input or stored data failed to (de)serialize |
+/// | `INTERNAL` | An error that should be impossible |
+/// | `ENTRY` | An entry could not be built |
+/// | `AGENT_TAG` | A link tag could not be read as an agent key |
+/// | `HOST` | A host function failed, `details` carries the host error |
+/// | `TIMESTAMP` | A timestamp was out of range |
+/// | `MISSING_LOCAL_HEADER` | A header just committed could not be found |
+/// | `WRONG_HEADER_TYPE` | A header without an entry was used where one was needed |
+/// | `MISSING_CHANNEL` | The channel doesn't exist, `details` is its path |
+/// | `DATA_FORMAT` | Stored data is in an unexpected format |
+/// | `INIT_FAILURE` | The membrane proof failed to validate |
+/// | `READ_ONLY` | This agent joined with a read-only membrane proof |
+/// | `INVALID_BATCHING_PATH` | A message bucket path is malformed |
+/// | `INVALID_CHANNEL_UUID` | The channel uuid isn't a uuid, `details` is the uuid |
+/// | `INVALID_CHANNEL_PATH` | A path doesn't describe a channel |
+/// | `INVALID_LINK_TAG` | A message link tag couldn't be decoded |
+/// | `NOT_CHANNEL_AUTHOR` | Only the author of a channel's info can change it |
+/// | `NOT_CHANNEL_UUID_OWNER` | A new channel's uuid must name its creator as the owner, `details` is the uuid |
+/// | `MISSING_CATEGORY` | The category isn't registered, `details` is the category |
+/// | `CATEGORY_EXISTS` | The category is already registered, `details` is the category |
+/// | `NOT_CATEGORY_AUTHOR` | Only the author of a category's info can change it |
+/// | `INVALID_CURSOR` | A list cursor couldn't be decoded |
+/// | `NOT_AUTHORIZED` | The caller's channel role doesn't allow this |
+/// | `SILENCED` | The caller is banned or muted on the channel |
+/// | `ALREADY_REPORTED` | The caller already reported this message |
+/// | `RATE_LIMITED` | The caller sent too many messages in the last minute |
+/// | `SLOW_MODE` | The caller sent a message on this slow mode channel too recently |
+/// | `GENERIC` | Anything else, `details` describes it |
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ChatErrorCode {
+    Serialization,
+    Internal,
+    Entry,
+    AgentTag,
+    Host,
+    Timestamp,
+    MissingLocalHeader,
+    WrongHeaderType,
+    MissingChannel,
+    DataFormat,
+    InitFailure,
+    ReadOnly,
+    InvalidBatchingPath,
+    InvalidChannelUuid,
+    InvalidChannelPath,
+    InvalidLinkTag,
+    NotChannelAuthor,
+    NotChannelUuidOwner,
+    MissingCategory,
+    CategoryExists,
+    NotCategoryAuthor,
+    InvalidCursor,
+    NotAuthorized,
+    Silenced,
+    AlreadyReported,
+    RateLimited,
+    SlowMode,
+    Generic,
+}
+
+/// The error clients receive, serialized as JSON inside `WasmError::Guest`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ChatErrorEnvelope {
+    pub code: ChatErrorCode,
+    pub message: String,
+    pub details: Option<String>,
+}
+
+impl ChatErrorEnvelope {
+    /// Read an envelope back out of a `WasmError::Guest` string
+    pub fn from_guest(guest: &str) -> Option<Self> {
+        serde_json::from_str(guest).ok()
+    }
+}
+
+impl ChatError {
+    pub fn code(&self) -> ChatErrorCode {
+        match self {
+            ChatError::Serialization(_) => ChatErrorCode::Serialization,
+            ChatError::Infallible(_) => ChatErrorCode::Internal,
+            ChatError::EntryError(_) => ChatErrorCode::Entry,
+            ChatError::AgentTag => ChatErrorCode::AgentTag,
+            ChatError::Wasm(_) => ChatErrorCode::Host,
+            ChatError::Timestamp(_) => ChatErrorCode::Timestamp,
+            ChatError::MissingLocalHeader => ChatErrorCode::MissingLocalHeader,
+            ChatError::WrongHeaderType => ChatErrorCode::WrongHeaderType,
+            ChatError::MissingChannel(_) => ChatErrorCode::MissingChannel,
+            ChatError::DataFormatError(_) => ChatErrorCode::DataFormat,
+            ChatError::InitFailure => ChatErrorCode::InitFailure,
+            ChatError::ReadOnly => ChatErrorCode::ReadOnly,
+            ChatError::InvalidBatchingPath => ChatErrorCode::InvalidBatchingPath,
+            ChatError::InvalidChannelUuid(_) => ChatErrorCode::InvalidChannelUuid,
+            ChatError::InvalidChannelPath => ChatErrorCode::InvalidChannelPath,
+            ChatError::InvalidLinkTag => ChatErrorCode::InvalidLinkTag,
+            ChatError::NotChannelAuthor => ChatErrorCode::NotChannelAuthor,
+            ChatError::NotChannelUuidOwner(_) => ChatErrorCode::NotChannelUuidOwner,
+            ChatError::MissingCategory(_) => ChatErrorCode::MissingCategory,
+            ChatError::CategoryExists(_) => ChatErrorCode::CategoryExists,
+            ChatError::NotCategoryAuthor => ChatErrorCode::NotCategoryAuthor,
+            ChatError::InvalidCursor => ChatErrorCode::InvalidCursor,
+            ChatError::NotAuthorized => ChatErrorCode::NotAuthorized,
+            ChatError::Silenced => ChatErrorCode::Silenced,
+            ChatError::AlreadyReported => ChatErrorCode::AlreadyReported,
+            ChatError::RateLimited => ChatErrorCode::RateLimited,
+            ChatError::SlowMode => ChatErrorCode::SlowMode,
+            ChatError::Generic(_) => ChatErrorCode::Generic,
+        }
+    }
+
+    /// The variable part of the error, if any
+    pub fn details(&self) -> Option<String> {
+        match self {
+            ChatError::Wasm(e) => Some(format!("{:?}", e)),
+            ChatError::MissingChannel(path) => Some(path.clone()),
+            ChatError::DataFormatError(s) | ChatError::Generic(s) => Some(s.to_string()),
+            ChatError::InvalidChannelUuid(uuid) | ChatError::NotChannelUuidOwner(uuid) => {
+                Some(uuid.clone())
+            }
+            ChatError::MissingCategory(category) | ChatError::CategoryExists(category) => {
+                Some(category.clone())
+            }
+            _ => None,
+        }
+    }
+}
+
+impl From<&ChatError> for ChatErrorEnvelope {
+    fn from(c: &ChatError) -> Self {
+        Self {
+            code: c.code(),
+            message: c.to_string(),
+            details: c.details(),
+        }
+    }
+}
+
 impl From<ChatError> for WasmError {
     fn from(c: ChatError) -> Self {
-        WasmError::Guest(c.to_string())
+        let envelope = ChatErrorEnvelope::from(&c);
+        WasmError::Guest(serde_json::to_string(&envelope).unwrap_or_else(|_| c.to_string()))
     }
 }
@@ -1,16 +1,46 @@
-pub use channel::{Channel, ChannelData, ChannelInfo, ChannelInput, ChannelList, ChannelListInput};
-pub use entries::{channel, message};
-pub use error::{ChatError, ChatResult};
+pub use blocking::BlockList;
+pub use category::{
+    CategoryInfo, CategoryInput, CategoryList, MoveCategoryInput, RenameCategoryInput,
+};
+pub use channel::{
+    Channel, ChannelData, ChannelInfo, ChannelInput, ChannelList, ChannelListInput, ChannelSort,
+    RenameChannelInput, SlowModeInput,
+};
+pub use entries::{category, channel, message};
+pub use error::{ChatError, ChatErrorCode, ChatErrorEnvelope, ChatResult};
 pub use hc_joining_code;
 pub use hdk::prelude::Path;
 pub use hdk::prelude::*;
 pub use message::{
-    ActiveChatters, ListMessages, ListMessagesInput, Message, MessageData, MessageInput,
-    SigResults, SignalMessageData, SignalSpecificInput,
+    ActiveChatters, ChatterRefresh, CreatedMessage, LastSeen, ListMessages, ListMessagesInput,
+    Message, MessageData, MessageInput, MessagePreview, PresenceChanges, SigResults,
+    SignalChattersInput, SignalMessageData, SignalSpecificInput,
+};
+pub use moderation::{
+    BanInput, HideMessageInput, ModerationAction, ModerationKind, ModerationLog,
+    ModerationLogInput, MuteInput,
+};
+pub use outbox::{OutboxRecord, PendingMessages};
+pub use rate_limit::RateLimitConfig;
+pub use reports::{
+    CloseReportInput, ListReportsInput, Report, ReportData, ReportList, ReportMessageInput,
+    ReportStatus,
 };
+pub use roles::{ChannelRole, ChannelRoles, RoleInput};
+pub use signals::{RelaySignal, SignalFilter, SignalOptions, SignalReceipt};
+pub use sync::{SyncInput, SyncResult};
 pub mod batching_helper;
+pub mod blocking;
 pub mod entries;
 pub mod error;
+pub mod membership;
+pub mod moderation;
+pub mod outbox;
+pub mod rate_limit;
+pub mod reports;
+pub mod roles;
+pub mod signals;
+pub mod sync;
 pub mod utils;
 pub mod validation;
 
@@ -18,11 +48,12 @@ pub mod validation;
 pub const NEW_MESSAGE_SIGNAL_TYPE: &str = "new_message";
 pub const NEW_CHANNEL_SIGNAL_TYPE: &str = "new_channel";
 
-#[derive(Serialize, Deserialize, SerializedBytes, Debug)]
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone)]
 #[serde(tag = "signal_name", content = "signal_payload")]
 pub enum SignalPayload {
     Message(SignalMessageData),
     Channel(ChannelData),
+    Relay(RelaySignal),
 }
 
 // pub(crate) fn _signal_ui(signal: SignalPayload) -> ChatResult<()> {
@@ -43,16 +74,53 @@ pub enum SignalPayload {
 fn recv_remote_signal(signal: ExternIO) -> ExternResult<()> {
     let sig: SignalPayload = signal.decode()?;
     trace!("Received remote signal {:?}", sig);
-    Ok(emit_signal(&sig)?)
+    let sender = call_info()?.provenance;
+    if let Some(sig) = signals::receive_remote_signal(&sender, sig)? {
+        emit_signal(&sig)?;
+    }
+    Ok(())
+}
+
+#[hdk_extern]
+fn set_signal_filter(filter: SignalFilter) -> ExternResult<()> {
+    Ok(signals::set_signal_filter(filter)?)
+}
+
+#[hdk_extern]
+fn get_signal_filter(_: ()) -> ExternResult<SignalFilter> {
+    Ok(signals::get_signal_filter()?)
 }
 
 entry_defs![
     Path::entry_def(),
     PathEntry::entry_def(),
     Message::entry_def(),
-    ChannelInfo::entry_def()
+    ChannelInfo::entry_def(),
+    CategoryInfo::entry_def(),
+    SignalFilter::entry_def(),
+    SignalReceipt::entry_def(),
+    OutboxRecord::entry_def(),
+    ChatterRefresh::entry_def(),
+    ModerationAction::entry_def(),
+    BlockList::entry_def(),
+    Report::entry_def()
 ];
 
+#[hdk_extern]
+fn block_agent(agent: AgentPubKey) -> ExternResult<BlockList> {
+    Ok(blocking::block_agent(agent)?)
+}
+
+#[hdk_extern]
+fn unblock_agent(agent: AgentPubKey) -> ExternResult<BlockList> {
+    Ok(blocking::unblock_agent(agent)?)
+}
+
+#[hdk_extern]
+fn get_block_list(_: ()) -> ExternResult<BlockList> {
+    Ok(blocking::get_block_list()?)
+}
+
 #[hdk_extern]
 fn init(_: ()) -> ExternResult<InitCallbackResult> {
     // grant unrestricted access to accept_cap_claim so other agents can send us claims
@@ -67,19 +135,180 @@ fn init(_: ()) -> ExternResult<InitCallbackResult> {
     Ok(InitCallbackResult::Pass)
 }
 
-#[hdk_extern]
-fn create_channel(channel_input: ChannelInput) -> ExternResult<ChannelData> {
+/// Fail early with a clear error on a read-only instance, which validation rejects regardless,
+/// and make sure the read-only check of what we commit next stays short
+fn prepare_chat_commit() -> ExternResult<()> {
     if hc_joining_code::is_read_only_instance() {
         return Err(ChatError::ReadOnly.into());
     }
+    Ok(membership::ensure_membership_link()?)
+}
+
+/// A uuid for a new channel, naming us as its owner
+#[hdk_extern]
+fn new_channel_uuid(_: ()) -> ExternResult<String> {
+    Ok(channel::handlers::new_channel_uuid()?)
+}
+
+#[hdk_extern]
+fn create_channel(channel_input: ChannelInput) -> ExternResult<ChannelData> {
+    prepare_chat_commit()?;
     Ok(channel::handlers::create_channel(channel_input)?)
 }
 
+#[hdk_extern]
+fn rename_channel(input: RenameChannelInput) -> ExternResult<ChannelData> {
+    prepare_chat_commit()?;
+    Ok(channel::handlers::rename_channel(input)?)
+}
+
+#[hdk_extern]
+fn archive_channel(channel: Channel) -> ExternResult<ChannelData> {
+    prepare_chat_commit()?;
+    Ok(channel::handlers::archive_channel(channel)?)
+}
+
+/// Set or clear a channel's slow mode. Only the channel's owner can.
+#[hdk_extern]
+fn set_slow_mode(input: SlowModeInput) -> ExternResult<ChannelData> {
+    prepare_chat_commit()?;
+    Ok(channel::handlers::set_slow_mode(input)?)
+}
+
+#[hdk_extern]
+fn grant_role(input: RoleInput) -> ExternResult<()> {
+    prepare_chat_commit()?;
+    Ok(roles::grant_role(input)?)
+}
+
+#[hdk_extern]
+fn revoke_role(input: RoleInput) -> ExternResult<()> {
+    prepare_chat_commit()?;
+    Ok(roles::revoke_role(input)?)
+}
+
+#[hdk_extern]
+fn get_channel_roles(channel: Channel) -> ExternResult<ChannelRoles> {
+    Ok(roles::get_channel_roles(&channel)?)
+}
+
+/// Hide a message from everyone but the channel's moderators
+#[hdk_extern]
+fn hide_message(input: HideMessageInput) -> ExternResult<ModerationAction> {
+    prepare_chat_commit()?;
+    Ok(moderation::hide_message(input)?)
+}
+
+/// Ban an agent on a channel we own.
+/// Their messages from while they are banned are dropped when the channel is read.
+#[hdk_extern]
+fn ban_agent(input: BanInput) -> ExternResult<ModerationAction> {
+    prepare_chat_commit()?;
+    Ok(moderation::ban_agent(input)?)
+}
+
+/// Lift a ban or mute on a channel we own
+#[hdk_extern]
+fn unban_agent(input: BanInput) -> ExternResult<ModerationAction> {
+    prepare_chat_commit()?;
+    Ok(moderation::unban_agent(input)?)
+}
+
+/// Mute an agent on a channel we own for a while.
+/// Their messages from while they are muted are dropped when the channel is read.
+#[hdk_extern]
+fn mute_agent(input: MuteInput) -> ExternResult<ModerationAction> {
+    prepare_chat_commit()?;
+    Ok(moderation::mute_agent(input)?)
+}
+
+/// Report an abusive message to the channel's owner
+#[hdk_extern]
+fn report_message(input: ReportMessageInput) -> ExternResult<ReportData> {
+    prepare_chat_commit()?;
+    Ok(reports::report_message(input)?)
+}
+
+/// The reports on a channel we own
+#[hdk_extern]
+fn list_reports(input: ListReportsInput) -> ExternResult<ReportList> {
+    Ok(reports::list_reports(input)?)
+}
+
+#[hdk_extern]
+fn resolve_report(input: CloseReportInput) -> ExternResult<ModerationAction> {
+    prepare_chat_commit()?;
+    Ok(reports::resolve_report(input)?)
+}
+
+#[hdk_extern]
+fn dismiss_report(input: CloseReportInput) -> ExternResult<ModerationAction> {
+    prepare_chat_commit()?;
+    Ok(reports::dismiss_report(input)?)
+}
+
+#[hdk_extern]
+fn get_moderation_log(input: ModerationLogInput) -> ExternResult<ModerationLog> {
+    Ok(moderation::get_moderation_log(input)?)
+}
+
+#[hdk_extern]
+fn create_category(input: CategoryInput) -> ExternResult<CategoryInfo> {
+    prepare_chat_commit()?;
+    Ok(category::handlers::create_category(input)?)
+}
+
+#[hdk_extern]
+fn rename_category(input: RenameCategoryInput) -> ExternResult<CategoryInfo> {
+    prepare_chat_commit()?;
+    Ok(category::handlers::rename_category(input)?)
+}
+
+#[hdk_extern]
+fn move_category(input: MoveCategoryInput) -> ExternResult<CategoryInfo> {
+    prepare_chat_commit()?;
+    Ok(category::handlers::move_category(input)?)
+}
+
+#[hdk_extern]
+fn list_categories(_: ()) -> ExternResult<CategoryList> {
+    Ok(category::handlers::list_categories()?)
+}
+
 #[hdk_extern]
 fn validate(op: Op) -> ExternResult<ValidateCallbackResult> {
     // validation::common_validatation(data)
     match op {
-        Op::StoreEntry { entry, .. } => validation::__validate_create_entry(entry),
+        Op::StoreEntry { header, entry } => {
+            validation::__validate_create_entry(&header.hashed.content, entry)
+        }
+        Op::RegisterCreateLink { create_link } => {
+            validation::__validate_create_link(&create_link.hashed.content)
+        }
+        Op::StoreElement { element } => validation::__validate_store_element(&element),
+        Op::RegisterUpdate {
+            header,
+            original_header,
+            ..
+        } => Ok(validation::validate_original_author(
+            &header.hashed.content.author,
+            original_header.author(),
+        )),
+        Op::RegisterDelete {
+            header,
+            original_header,
+            ..
+        } => Ok(validation::validate_original_author(
+            &header.hashed.content.author,
+            original_header.author(),
+        )),
+        Op::RegisterDeleteLink {
+            delete_link,
+            create_link,
+        } => Ok(validation::validate_original_author(
+            &delete_link.hashed.content.author,
+            &create_link.author,
+        )),
         _ => Ok(ValidateCallbackResult::Valid),
     }
 }
@@ -91,16 +320,34 @@ fn insert_fake_messages(input: message::handlers::InsertFakeMessagesPayload) ->
 }
 
 #[hdk_extern]
-fn create_message(message_input: MessageInput) -> ExternResult<MessageData> {
-    if hc_joining_code::is_read_only_instance() {
-        return Err(ChatError::ReadOnly.into());
-    }
-    Ok(message::handlers::create_message(
+fn link_fake_message(input: message::handlers::LinkFakeMessagePayload) -> ExternResult<()> {
+    message::handlers::link_fake_message(input)?;
+    Ok(())
+}
+
+/// Create a message and signal it to the active chatters.
+/// Creating a message again with the same uuid on the same channel returns the first one,
+/// as long as it is within the last [`message::handlers::RETRY_WINDOW_HEADERS`] headers of our chain.
+#[hdk_extern]
+fn create_message(message_input: MessageInput) -> ExternResult<CreatedMessage> {
+    prepare_chat_commit()?;
+    Ok(message::handlers::create_and_signal_message(
         message_input,
         sys_time()?,
     )?)
 }
 
+/// Create many messages at once, e.g. when importing history.
+/// The messages are returned in the order they were given and aren't signalled.
+#[hdk_extern]
+fn create_messages(message_inputs: Vec<MessageInput>) -> ExternResult<Vec<MessageData>> {
+    prepare_chat_commit()?;
+    Ok(message::handlers::create_messages(
+        message_inputs,
+        sys_time()?,
+    )?)
+}
+
 /*#[hdk_extern]
 fn signal_users_on_channel(message_data SignalMessageData) -> ChatResult<()> {
     message::handlers::signal_users_on_channel(message_data)
@@ -111,6 +358,11 @@ fn get_active_chatters(_: ()) -> ExternResult<ActiveChatters> {
     Ok(message::handlers::get_active_chatters()?)
 }
 
+#[hdk_extern]
+fn get_channel_chatters(channel: Channel) -> ExternResult<ActiveChatters> {
+    Ok(message::handlers::get_channel_chatters(&channel)?)
+}
+
 #[hdk_extern]
 fn signal_specific_chatters(input: SignalSpecificInput) -> ExternResult<()> {
     if hc_joining_code::is_read_only_instance() {
@@ -124,7 +376,21 @@ fn signal_chatters(message_data: SignalMessageData) -> ExternResult<SigResults>
     if hc_joining_code::is_read_only_instance() {
         return Err(ChatError::ReadOnly.into());
     }
-    Ok(message::handlers::signal_chatters(message_data)?)
+    Ok(message::handlers::signal_chatters(
+        message_data,
+        SignalOptions::default(),
+    )?)
+}
+
+#[hdk_extern]
+fn signal_chatters_with_options(input: SignalChattersInput) -> ExternResult<SigResults> {
+    if hc_joining_code::is_read_only_instance() {
+        return Err(ChatError::ReadOnly.into());
+    }
+    Ok(message::handlers::signal_chatters(
+        input.signal_message_data,
+        input.options,
+    )?)
 }
 
 #[hdk_extern]
@@ -147,6 +413,26 @@ fn list_messages(list_messages_input: ListMessagesInput) -> ExternResult<ListMes
     Ok(message::handlers::list_messages(list_messages_input)?)
 }
 
+#[hdk_extern]
+fn list_pending_messages(_: ()) -> ExternResult<PendingMessages> {
+    Ok(outbox::list_pending()?)
+}
+
+/// Meant to be called periodically, e.g. by a timer in the UI,
+/// until it returns no pending messages
+#[hdk_extern]
+fn retry_pending(_: ()) -> ExternResult<PendingMessages> {
+    if hc_joining_code::is_read_only_instance() {
+        return Err(ChatError::ReadOnly.into());
+    }
+    Ok(outbox::retry_pending()?)
+}
+
+#[hdk_extern]
+fn sync_since(input: SyncInput) -> ExternResult<SyncResult> {
+    Ok(sync::sync_since(input)?)
+}
+
 #[derive(Debug, Serialize, Deserialize, SerializedBytes, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct ChannelMessages {
@@ -0,0 +1,57 @@
+//! Keeping the read-only check on chat data short.
+//!
+//! Validation rejects chat data from agents that joined with a read-only membrane proof,
+//! which is on their `AgentValidationPkg` at the start of their chain.
+//! Rather than walking back to it, the check stops at the author's last chat link,
+//! which passed the same check, or at a membership link, which cites the author's pkg header.
+//! Either must be within `READ_ONLY_CHECK_WINDOW_HEADERS` headers of the data being checked,
+//! so before committing chat data we link our membership again if the last one is too far back.
+use crate::{
+    error::{ChatError, ChatResult},
+    utils::recent_elements,
+    validation::{is_chat_link, READ_ONLY_CHECK_WINDOW_HEADERS},
+};
+use hdk::prelude::*;
+
+/// Tags the link from an agent to themselves that cites their `AgentValidationPkg` header
+pub(crate) struct MembershipTag;
+
+impl MembershipTag {
+    const TAG: &'static [u8; 10] = b"membership";
+
+    /// Create the tag citing this pkg header
+    pub(crate) fn tag(pkg: &HeaderHash) -> LinkTag {
+        LinkTag::new([&Self::TAG[..], pkg.get_raw_39()].concat())
+    }
+
+    /// The pkg header cited by a membership tag, or `None` for any other tag
+    pub(crate) fn pkg(tag: &LinkTag) -> Option<HeaderHash> {
+        let bytes = tag.0.strip_prefix(&Self::TAG[..])?;
+        HeaderHash::from_raw_39(bytes.to_vec()).ok()
+    }
+}
+
+/// Link our membership unless the read-only check of whatever we commit next
+/// will find our pkg, a chat link or a membership link close by.
+/// Only half the window is looked at, leaving the rest for the path links committed along the way.
+pub(crate) fn ensure_membership_link() -> ChatResult<()> {
+    let recent = recent_elements(READ_ONLY_CHECK_WINDOW_HEADERS / 2, QueryFilter::new())?;
+    let close_by = recent.iter().any(|element| match element.header() {
+        Header::AgentValidationPkg(_) => true,
+        Header::CreateLink(create_link) => {
+            is_chat_link(&create_link.tag) || MembershipTag::pkg(&create_link.tag).is_some()
+        }
+        _ => false,
+    });
+    if close_by {
+        return Ok(());
+    }
+    let pkg = query(QueryFilter::new().header_type(HeaderType::AgentValidationPkg))?
+        .into_iter()
+        .next()
+        .map(|element| element.header_address().clone())
+        .ok_or(ChatError::Generic("Our chain has no validation pkg"))?;
+    let me: EntryHash = agent_info()?.agent_initial_pubkey.into();
+    create_link(me.clone(), me, HdkLinkType::Any, MembershipTag::tag(&pkg))?;
+    Ok(())
+}
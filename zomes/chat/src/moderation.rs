@@ -0,0 +1,372 @@
+//! Actions moderators take on a channel, and the log that records them.
+//!
+//! Every action is a public entry linked from its channel and from its subject, e.g. the hidden message.
+//! The channel's links are read once for both its roles and its actions.
+//! The link tag repeats what the action did, so readers can tell which messages
+//! are hidden and who is banned or muted without getting the actions.
+//!
+//! Validation checks that every action was taken by someone allowed to, and readers
+//! replay the actions against the channel's roles so a revocation takes effect for them at once.
+//! Validation can only read data by hash, so it can't prove an agent has no ban or mute
+//! on a channel when their message is linked. Bans and mutes are instead applied when messages are read:
+//! a message is dropped for everyone if its header was written while its author was banned or muted.
+//! Headers can't be dated before the one before them, so messages from before a sanction stay listed.
+use crate::{
+    channel::Channel,
+    error::{ChatError, ChatResult},
+    message::MessageData,
+    roles::{channel_links, get_role_state, role_state_from_links, ChannelRole, RoleState},
+};
+use hdk::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+/// Longest moderation reason in characters
+pub const MAX_MODERATION_REASON_LENGTH: usize = 500;
+
+/// What a moderation action did
+#[derive(Debug, Clone, Serialize, Deserialize, SerializedBytes, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ModerationKind {
+    /// Hide the message with this entry hash from everyone but moderators
+    HideMessage(EntryHash),
+    /// Stop the agent posting on the channel until they are unbanned
+    Ban(AgentPubKey),
+    /// Lift a ban or mute
+    Unban(AgentPubKey),
+    /// Stop the agent posting on the channel until `until`
+    Mute {
+        agent: AgentPubKey,
+        until: Timestamp,
+    },
+    /// Close the report with this entry hash as acted on, see `reports`
+    ResolveReport(EntryHash),
+    /// Close the report with this entry hash as needing no action
+    DismissReport(EntryHash),
+}
+
+impl ModerationKind {
+    /// What the action is about. The action is also linked from here.
+    pub fn subject(&self) -> EntryHash {
+        match self {
+            ModerationKind::HideMessage(message_hash) => message_hash.clone(),
+            ModerationKind::ResolveReport(report_hash)
+            | ModerationKind::DismissReport(report_hash) => report_hash.clone(),
+            ModerationKind::Ban(agent)
+            | ModerationKind::Unban(agent)
+            | ModerationKind::Mute { agent, .. } => agent.clone().into(),
+        }
+    }
+
+    /// The role needed to take this action
+    pub fn needed_role(&self) -> ChannelRole {
+        match self {
+            ModerationKind::HideMessage(_) => ChannelRole::Moderator,
+            ModerationKind::Ban(_)
+            | ModerationKind::Unban(_)
+            | ModerationKind::Mute { .. }
+            | ModerationKind::ResolveReport(_)
+            | ModerationKind::DismissReport(_) => ChannelRole::Owner,
+        }
+    }
+}
+
+/// A moderation action, saved publicly so anyone can audit it
+#[hdk_entry(id = "moderation_action")]
+#[derive(Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ModerationAction {
+    pub channel: Channel,
+    pub kind: ModerationKind,
+    pub reason: String,
+    pub moderator: AgentPubKey,
+    pub created_at: Timestamp,
+    /// The header showing the moderator had a role on the channel, see `roles`
+    pub authority: HeaderHash,
+}
+
+/// Input to the hide message call
+#[derive(Debug, Serialize, Deserialize, SerializedBytes)]
+#[serde(rename_all = "camelCase")]
+pub struct HideMessageInput {
+    pub channel: Channel,
+    pub message_hash: EntryHash,
+    pub reason: String,
+}
+
+/// Input to the ban agent and unban agent calls
+#[derive(Debug, Serialize, Deserialize, SerializedBytes)]
+#[serde(rename_all = "camelCase")]
+pub struct BanInput {
+    pub channel: Channel,
+    pub agent: AgentPubKey,
+    pub reason: String,
+}
+
+/// Input to the mute agent call
+#[derive(Debug, Serialize, Deserialize, SerializedBytes)]
+#[serde(rename_all = "camelCase")]
+pub struct MuteInput {
+    pub channel: Channel,
+    pub agent: AgentPubKey,
+    pub reason: String,
+    /// How long the mute lasts
+    pub seconds: u64,
+}
+
+/// Input to the moderation log call
+#[derive(Debug, Serialize, Deserialize, SerializedBytes)]
+#[serde(rename_all = "camelCase")]
+pub struct ModerationLogInput {
+    pub channel: Channel,
+    /// Only actions taken by this moderator
+    #[serde(default)]
+    pub moderator: Option<AgentPubKey>,
+    /// Only actions taken after this time
+    #[serde(default)]
+    pub since: Option<Timestamp>,
+}
+
+/// A channel's moderation actions, oldest first
+#[derive(Debug, Serialize, Deserialize, SerializedBytes, derive_more::From)]
+pub struct ModerationLog {
+    pub actions: Vec<ModerationAction>,
+}
+
+/// Tags the links to a moderation action.
+/// The tag holds the action's kind so it can be read without getting the action.
+pub(crate) struct ModerationTag;
+
+impl ModerationTag {
+    const TAG: &'static [u8; 10] = b"moderation";
+
+    /// The prefix every moderation tag starts with
+    pub(crate) fn any() -> LinkTag {
+        LinkTag::new(*Self::TAG)
+    }
+
+    pub(crate) fn tag(kind: &ModerationKind) -> ChatResult<LinkTag> {
+        let bytes: Vec<u8> = UnsafeBytes::from(SerializedBytes::try_from(kind.clone())?).into();
+        Ok(LinkTag::new([&Self::TAG[..], &bytes].concat()))
+    }
+
+    /// The action kind in a moderation tag, or `None` for any other tag
+    pub(crate) fn parse(tag: &LinkTag) -> Option<ModerationKind> {
+        let bytes = tag.0.strip_prefix(&Self::TAG[..])?;
+        ModerationKind::try_from(SerializedBytes::from(UnsafeBytes::from(bytes.to_vec()))).ok()
+    }
+}
+
+pub(crate) fn hide_message(input: HideMessageInput) -> ChatResult<ModerationAction> {
+    let HideMessageInput {
+        channel,
+        message_hash,
+        reason,
+    } = input;
+    take_action(channel, ModerationKind::HideMessage(message_hash), reason)
+}
+
+pub(crate) fn ban_agent(input: BanInput) -> ChatResult<ModerationAction> {
+    let BanInput {
+        channel,
+        agent,
+        reason,
+    } = input;
+    take_action(channel, ModerationKind::Ban(agent), reason)
+}
+
+pub(crate) fn unban_agent(input: BanInput) -> ChatResult<ModerationAction> {
+    let BanInput {
+        channel,
+        agent,
+        reason,
+    } = input;
+    take_action(channel, ModerationKind::Unban(agent), reason)
+}
+
+pub(crate) fn mute_agent(input: MuteInput) -> ChatResult<ModerationAction> {
+    let MuteInput {
+        channel,
+        agent,
+        reason,
+        seconds,
+    } = input;
+    let until = (sys_time()? + std::time::Duration::from_secs(seconds))?;
+    take_action(channel, ModerationKind::Mute { agent, until }, reason)
+}
+
+/// Commit an action taken by us on a channel we moderate
+pub(crate) fn take_action(
+    channel: Channel,
+    kind: ModerationKind,
+    reason: String,
+) -> ChatResult<ModerationAction> {
+    let me = agent_info()?.agent_initial_pubkey;
+    let (_, authority) = get_role_state(&channel)?
+        .authority(&me)
+        .filter(|(role, _)| *role >= kind.needed_role())
+        .ok_or(ChatError::NotAuthorized)?;
+    let action = ModerationAction {
+        channel,
+        kind,
+        reason,
+        moderator: me,
+        created_at: sys_time()?,
+        authority,
+    };
+    create_entry(&action)?;
+    let action_hash = hash_entry(&action)?;
+
+    let tag = ModerationTag::tag(&action.kind)?;
+    let path: Path = action.channel.clone().try_into()?;
+    create_link(
+        path.path_entry_hash()?,
+        action_hash.clone(),
+        HdkLinkType::Any,
+        tag.clone(),
+    )?;
+    create_link(action.kind.subject(), action_hash, HdkLinkType::Any, tag)?;
+    Ok(action)
+}
+
+/// What moderators have done to a channel's messages and posters
+pub(crate) struct ChannelModeration {
+    hidden: HashSet<EntryHash>,
+    /// Each sanctioned agent's bans, unbans and mutes, oldest first
+    sanctions: HashMap<AgentPubKey, Vec<(Timestamp, ModerationKind)>>,
+}
+
+impl ChannelModeration {
+    /// Whether a moderator hid this message
+    pub(crate) fn is_hidden(&self, message_hash: &EntryHash) -> bool {
+        self.hidden.contains(message_hash)
+    }
+
+    /// Whether `agent` was banned or muted on the channel at `at`
+    pub(crate) fn is_silenced(&self, agent: &AgentPubKey, at: Timestamp) -> bool {
+        let actions = match self.sanctions.get(agent) {
+            Some(actions) => actions,
+            None => return false,
+        };
+        let mut banned = false;
+        let mut muted_until = None;
+        for (_, kind) in actions.iter().take_while(|(t, _)| *t <= at) {
+            match kind {
+                ModerationKind::Ban(_) => banned = true,
+                ModerationKind::Unban(_) => {
+                    banned = false;
+                    muted_until = None;
+                }
+                ModerationKind::Mute { until, .. } => muted_until = Some(*until),
+                _ => (),
+            }
+        }
+        banned || muted_until.map_or(false, |until| at < until)
+    }
+
+    /// Whether this message should only be shown to moderators
+    pub(crate) fn is_removed(&self, message: &MessageData) -> bool {
+        self.is_hidden(&message.entry_hash)
+            || self.is_silenced(&message.created_by, message.created_at)
+    }
+}
+
+/// The moderation links on a channel, oldest first, with the action each records.
+/// Only links made by an agent who held the role the action needs at the time are kept.
+/// Validation checks the same, but can't see revocations so it still honours a revoked grant.
+pub(crate) fn authorized_actions(
+    links: Vec<(HeaderHash, CreateLink)>,
+    roles: &RoleState,
+) -> Vec<(CreateLink, ModerationKind)> {
+    let mut actions: Vec<(CreateLink, ModerationKind)> = links
+        .into_iter()
+        .filter_map(|(_, link)| {
+            let kind = ModerationTag::parse(&link.tag)?;
+            let held = roles.role_at(&link.author, link.timestamp)?;
+            if held >= kind.needed_role() {
+                Some((link, kind))
+            } else {
+                None
+            }
+        })
+        .collect();
+    actions.sort_by_key(|(link, _)| link.timestamp);
+    actions
+}
+
+/// Read what moderators have done to a channel from the link tags alone
+pub(crate) fn get_channel_moderation(channel: &Channel) -> ChatResult<ChannelModeration> {
+    let links = channel_links(channel)?;
+    let roles = role_state_from_links(channel, &links)?;
+    moderation_from_links(links, &roles)
+}
+
+/// What moderators have done, from a channel's links and the roles replayed from them
+pub(crate) fn moderation_from_links(
+    links: Vec<(HeaderHash, CreateLink)>,
+    roles: &RoleState,
+) -> ChatResult<ChannelModeration> {
+    let mut moderation = ChannelModeration {
+        hidden: HashSet::new(),
+        sanctions: HashMap::new(),
+    };
+    for (link, kind) in authorized_actions(links, roles) {
+        match kind {
+            ModerationKind::HideMessage(message_hash) => {
+                moderation.hidden.insert(message_hash);
+            }
+            kind @ (ModerationKind::Ban(_)
+            | ModerationKind::Unban(_)
+            | ModerationKind::Mute { .. }) => {
+                let agent = AgentPubKey::from_raw_36(kind.subject().get_raw_36().to_vec());
+                moderation
+                    .sanctions
+                    .entry(agent)
+                    .or_default()
+                    .push((link.timestamp, kind));
+            }
+            _ => (),
+        }
+    }
+    Ok(moderation)
+}
+
+/// Whether we may see what moderators see on this channel
+pub(crate) fn is_moderator(channel: &Channel) -> ChatResult<bool> {
+    let me = agent_info()?.agent_initial_pubkey;
+    Ok(get_role_state(channel)?
+        .roles
+        .has_role(&me, ChannelRole::Moderator))
+}
+
+pub(crate) fn get_moderation_log(input: ModerationLogInput) -> ChatResult<ModerationLog> {
+    let ModerationLogInput {
+        channel,
+        moderator,
+        since,
+    } = input;
+    let links = channel_links(&channel)?;
+    let roles = role_state_from_links(&channel, &links)?;
+    let action_results_input: Vec<GetInput> = authorized_actions(links, &roles)
+        .into_iter()
+        .filter(|(link, _)| since.map_or(true, |since| link.timestamp > since))
+        .map(|(link, _)| GetInput::new(link.target_address.into(), GetOptions::default()))
+        .collect();
+    if action_results_input.is_empty() {
+        return Ok(Vec::new().into());
+    }
+    let all_action_results_elements = HDK.with(|hdk| hdk.borrow().get(action_results_input))?;
+
+    let mut actions = Vec::new();
+    for element in all_action_results_elements.into_iter().flatten() {
+        let action = match element.into_inner().1.to_app_option::<ModerationAction>() {
+            Ok(Some(action)) => action,
+            // Skip anything on the moderation links that isn't an action
+            _ => continue,
+        };
+        if moderator.as_ref().map_or(true, |m| *m == action.moderator) {
+            actions.push(action);
+        }
+    }
+    actions.sort_by_key(|action| action.created_at);
+    Ok(actions.into())
+}
@@ -0,0 +1,154 @@
+//! A local record of the messages we authored that may not have reached anyone yet.
+//!
+//! While partitioned, `create_message` still commits but the signal can fail
+//! and the publish to the DHT can lag. Every message we create gets an outbox record,
+//! and stays unconfirmed until a `get` of its header, which asks the network for the latest, finds it.
+//! Confirming is saved on a new record so confirmed messages aren't fetched again.
+//!
+//! `retry_pending` re-signals, and commits a record for each message whose state changed.
+//! Committing wakes the conductor's publish workflow, which publishes again whatever
+//! the network hasn't acknowledged, up to `OUTBOX_MAX_REPUBLISHES` times per message.
+//! Only the last `OUTBOX_WINDOW_HEADERS` headers of our chain are looked at,
+//! so older messages drop out of the outbox.
+use crate::{
+    channel::Channel,
+    error::ChatResult,
+    message::{handlers::signal_new_message, MessageData},
+    utils::recent_elements,
+};
+use hdk::prelude::*;
+use std::collections::HashMap;
+
+/// How far back on our chain the outbox looks for messages
+pub const OUTBOX_WINDOW_HEADERS: u32 = 1000;
+/// How many times `retry_pending` re-publishes a message the network hasn't seen
+pub const OUTBOX_MAX_REPUBLISHES: u32 = 5;
+
+/// The delivery state of one of our messages.
+/// Records are saved privately on the source chain when a message is created,
+/// each change commits a new record and the latest one wins.
+#[hdk_entry(id = "outbox_record", visibility = "private")]
+#[derive(Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct OutboxRecord {
+    /// The header of the message entry, fetched to confirm the message
+    pub header_hash: HeaderHash,
+    pub message: MessageData,
+    pub channel: Channel,
+    /// The message was meant to be signalled but that hasn't succeeded yet
+    pub needs_signal: bool,
+    /// The message's header could be fetched from the network
+    pub confirmed: bool,
+    /// How many times `retry_pending` has re-published the message
+    #[serde(default)]
+    pub republished: u32,
+}
+
+impl OutboxRecord {
+    pub fn is_pending(&self) -> bool {
+        self.needs_signal || !self.confirmed
+    }
+}
+
+/// The messages still waiting to be signalled or confirmed
+#[derive(Debug, Serialize, Deserialize, SerializedBytes, derive_more::From)]
+pub struct PendingMessages {
+    pub messages: Vec<OutboxRecord>,
+}
+
+/// Record a message we just created, noting if its signal failed
+pub(crate) fn record(
+    header_hash: HeaderHash,
+    message: MessageData,
+    channel: Channel,
+    needs_signal: bool,
+) -> ChatResult<()> {
+    create_entry(&OutboxRecord {
+        header_hash,
+        message,
+        channel,
+        needs_signal,
+        confirmed: false,
+        republished: 0,
+    })?;
+    Ok(())
+}
+
+pub(crate) fn list_pending() -> ChatResult<PendingMessages> {
+    let mut messages = Vec::new();
+    for mut record in latest_records()? {
+        if !record.confirmed {
+            record.confirmed = is_on_network(&record.header_hash)?;
+        }
+        if record.is_pending() {
+            messages.push(record);
+        }
+    }
+    messages.sort_unstable_by_key(|record| record.message.created_at);
+    Ok(messages.into())
+}
+
+/// The latest saved record of each message in the window
+fn latest_records() -> ChatResult<Vec<OutboxRecord>> {
+    let records = recent_elements(
+        OUTBOX_WINDOW_HEADERS,
+        QueryFilter::new()
+            .entry_type(entry_type!(OutboxRecord)?)
+            .include_entries(true),
+    )?;
+
+    let mut latest: HashMap<EntryHash, (u32, OutboxRecord)> = HashMap::new();
+    for element in records.iter() {
+        let seq = element.header().header_seq();
+        let record = match element.entry().to_app_option::<OutboxRecord>()? {
+            Some(record) => record,
+            None => continue,
+        };
+        match latest.get(&record.message.entry_hash) {
+            Some((latest_seq, _)) if *latest_seq > seq => (),
+            _ => {
+                latest.insert(record.message.entry_hash.clone(), (seq, record));
+            }
+        }
+    }
+    Ok(latest.into_values().map(|(_, record)| record).collect())
+}
+
+/// Whether the network has a message's header.
+/// Asking for the latest makes the `get` go to the authorities rather than settle for our cache.
+fn is_on_network(header_hash: &HeaderHash) -> ChatResult<bool> {
+    Ok(get(header_hash.clone(), GetOptions::latest())?.is_some())
+}
+
+/// Try to signal and re-publish every pending message, returning those still pending.
+/// Messages found to be confirmed get a record saying so.
+pub(crate) fn retry_pending() -> ChatResult<PendingMessages> {
+    let mut still_pending = Vec::new();
+    for record in latest_records()? {
+        if !record.is_pending() {
+            continue;
+        }
+        let mut updated = record.clone();
+        if !updated.confirmed {
+            updated.confirmed = is_on_network(&updated.header_hash)?;
+        }
+        if updated.needs_signal {
+            match signal_new_message(updated.message.clone(), updated.channel.clone()) {
+                Ok(_) => updated.needs_signal = false,
+                Err(e) => debug!("Failed to signal pending message: {:?}", e),
+            }
+        }
+        if !updated.confirmed && updated.republished < OUTBOX_MAX_REPUBLISHES {
+            updated.republished += 1;
+        }
+        // Only commit when something changed so retries don't grow the chain for ever
+        if updated != record {
+            create_entry(&updated)?;
+        }
+        if updated.is_pending() {
+            still_pending.push(updated);
+        }
+    }
+    still_pending.sort_unstable_by_key(|record| record.message.created_at);
+    Ok(still_pending.into())
+}
@@ -0,0 +1,113 @@
+//! Limits on how fast an agent can post messages.
+//!
+//! The limits are checked in validation against the author's own chain,
+//! which every validator can read, so a flood is rejected by all of them and not just by polite clients.
+//! The per-minute limit and the default slow mode are read from the DNA properties.
+//! Both are off unless set, since importing history posts many messages at once.
+//!
+//! A channel's owner can set its own slow mode on the channel info. Every message link
+//! cites the channel info link its author went by, which validators fetch by hash.
+//! Validation can't tell whether that info is the latest one, so a client that cites an
+//! older info, or none, is held to the slow mode of what it cites.
+use crate::{
+    channel::{Channel, ChannelInfo},
+    error::ChatError,
+    message::LastSeenKey,
+};
+use hdk::prelude::*;
+
+/// The longest slow mode a channel's owner can set
+pub const MAX_SLOW_MODE_SECONDS: u32 = 60 * 60;
+
+/// Message rate limits, read from the DNA properties.
+/// Missing properties leave the limit off.
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, Default, PartialEq, Eq)]
+pub struct RateLimitConfig {
+    /// The most messages an agent may post in a minute, across all channels
+    #[serde(default)]
+    pub max_messages_per_minute: Option<u32>,
+    /// The least time between an agent's messages on the same channel,
+    /// unless the channel's info sets its own
+    #[serde(default)]
+    pub slow_mode_seconds: Option<u32>,
+}
+
+impl RateLimitConfig {
+    /// A DNA without properties has no limits. Properties that don't parse are an error
+    /// rather than turning the limits off.
+    pub fn load() -> ExternResult<Self> {
+        let properties = dna_info()?.properties;
+        if matches!(properties.bytes().as_slice(), [] | [0xc0]) {
+            return Ok(Self::default());
+        }
+        Self::try_from(properties).map_err(|e| {
+            WasmError::Guest(format!(
+                "The rate limit DNA properties are malformed: {:?}",
+                e
+            ))
+        })
+    }
+
+    /// The slow mode on a channel with this info, if any
+    pub fn slow_mode(&self, info: Option<&ChannelInfo>) -> Option<u32> {
+        info.and_then(|info| info.slow_mode_seconds)
+            .or(self.slow_mode_seconds)
+            .filter(|seconds| *seconds > 0)
+    }
+}
+
+const MINUTE_MICROS: i64 = 60 * 1_000_000;
+
+/// Whether a message linked on `channel` at `now` would break the limits,
+/// given the author's chain up to `prev_header` and the channel info the message cites.
+///
+/// Walks back the chain only as far as the longest limit reaches,
+/// since headers on a chain are in time order.
+pub(crate) fn check_rate_limit(
+    config: &RateLimitConfig,
+    channel: &Channel,
+    info: Option<&ChannelInfo>,
+    now: Timestamp,
+    prev_header: HeaderHash,
+) -> ExternResult<Option<ChatError>> {
+    let per_minute = config.max_messages_per_minute;
+    let slow_mode = config.slow_mode(info).map(|s| s as i64 * 1_000_000);
+    let horizon = match (per_minute, slow_mode) {
+        (None, None) => return Ok(None),
+        (Some(_), slow_mode) => slow_mode.unwrap_or(0).max(MINUTE_MICROS),
+        (None, Some(slow_mode)) => slow_mode,
+    };
+
+    let mut in_last_minute = 0;
+    let mut header_hash = prev_header;
+    loop {
+        let signed_header = must_get_header(header_hash)?;
+        let header = signed_header.header();
+        let age = now.as_micros() - header.timestamp().as_micros();
+        if age >= horizon {
+            return Ok(None);
+        }
+        if let Header::CreateLink(create_link) = header {
+            // Only message links carry a last seen key with a channel
+            if let Ok(LastSeenKey {
+                channel: Some(linked_on),
+                ..
+            }) = LastSeenKey::try_from(create_link.tag.clone())
+            {
+                if age < MINUTE_MICROS {
+                    in_last_minute += 1;
+                }
+                if per_minute.map_or(false, |max| in_last_minute >= max) {
+                    return Ok(Some(ChatError::RateLimited));
+                }
+                if slow_mode.map_or(false, |slow_mode| linked_on == *channel && age < slow_mode) {
+                    return Ok(Some(ChatError::SlowMode));
+                }
+            }
+        }
+        match header.prev_header() {
+            Some(prev) => header_hash = prev.clone(),
+            None => return Ok(None),
+        }
+    }
+}
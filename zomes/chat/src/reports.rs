@@ -0,0 +1,257 @@
+//! Reports of abusive messages, queued for the channel's owner.
+//!
+//! A report is a public entry linked from the channel's report queue path.
+//! Each agent may only report a message once. Every report cites the header of its reporter's
+//! previous report, and validation walks that list back to reject a second report of the same message.
+//! A reporter can still start a new list by citing an older report or none,
+//! so `list_reports` also drops every report of a message but the reporter's first.
+//! The owner closes a report by resolving or dismissing it. Those are moderation actions,
+//! so they show up in the moderation log with the owner's note.
+use crate::{
+    channel::Channel,
+    error::{ChatError, ChatResult},
+    moderation::{authorized_actions, take_action, ModerationAction, ModerationKind},
+    roles::{channel_links, role_state_from_links, ChannelRole},
+};
+use hdk::{hash_path::path::Component, prelude::*};
+use std::collections::{HashMap, HashSet};
+
+/// Longest report reason in characters
+pub const MAX_REPORT_REASON_LENGTH: usize = 500;
+
+/// A report that a message is abusive
+#[hdk_entry(id = "report")]
+#[derive(Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Report {
+    pub channel: Channel,
+    pub message: EntryHash,
+    pub reason: String,
+    pub reporter: AgentPubKey,
+    pub created_at: Timestamp,
+    /// The header of the reporter's previous report, if they made one
+    #[serde(default)]
+    pub previous: Option<HeaderHash>,
+}
+
+/// Input to the report message call
+#[derive(Debug, Serialize, Deserialize, SerializedBytes)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportMessageInput {
+    pub channel: Channel,
+    pub message_hash: EntryHash,
+    pub reason: String,
+}
+
+/// Input to the resolve report and dismiss report calls
+#[derive(Debug, Serialize, Deserialize, SerializedBytes)]
+#[serde(rename_all = "camelCase")]
+pub struct CloseReportInput {
+    pub channel: Channel,
+    pub report_hash: EntryHash,
+    /// What was done about the report, recorded in the moderation log
+    pub note: String,
+}
+
+/// Input to the list reports call
+#[derive(Debug, Serialize, Deserialize, SerializedBytes)]
+#[serde(rename_all = "camelCase")]
+pub struct ListReportsInput {
+    pub channel: Channel,
+    /// Also list resolved and dismissed reports
+    #[serde(default)]
+    pub include_closed: Option<bool>,
+}
+
+/// Where a report is in the queue
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ReportStatus {
+    Open,
+    Resolved,
+    Dismissed,
+}
+
+/// A report as listed for the channel's owner
+#[derive(Debug, Serialize, Deserialize, SerializedBytes, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportData {
+    pub entry_hash: EntryHash,
+    pub report: Report,
+    pub status: ReportStatus,
+}
+
+/// The reports on a channel, oldest first
+#[derive(Debug, Serialize, Deserialize, SerializedBytes, derive_more::From)]
+pub struct ReportList {
+    pub reports: Vec<ReportData>,
+}
+
+/// Tags the links from a report queue to its reports
+pub(crate) struct ReportTag;
+
+impl ReportTag {
+    const TAG: &'static [u8; 6] = b"report";
+
+    /// Create the tag
+    pub(crate) fn tag() -> LinkTag {
+        LinkTag::new(*Self::TAG)
+    }
+}
+
+/// The reports on a channel are linked from here.
+/// This can't be below the channel's own path, where every child is a message bucket.
+pub fn reports_path(channel: &Channel) -> ChatResult<Path> {
+    let channel_path: Path = channel.clone().try_into()?;
+    let mut components: Vec<Component> = vec!["reports".into()];
+    components.append(&mut channel_path.into());
+    Ok(components.into())
+}
+
+pub(crate) fn report_message(input: ReportMessageInput) -> ChatResult<ReportData> {
+    let ReportMessageInput {
+        channel,
+        message_hash,
+        reason,
+    } = input;
+    let reports = my_reports()?;
+    if reports
+        .iter()
+        .any(|(_, report)| report.message == message_hash)
+    {
+        return Err(ChatError::AlreadyReported);
+    }
+    let report = Report {
+        channel,
+        message: message_hash,
+        reason,
+        reporter: agent_info()?.agent_initial_pubkey,
+        created_at: sys_time()?,
+        previous: reports.last().map(|(header_hash, _)| header_hash.clone()),
+    };
+    create_entry(&report)?;
+    let report_hash = hash_entry(&report)?;
+
+    let path = reports_path(&report.channel)?;
+    path.ensure()?;
+    create_link(
+        path.path_entry_hash()?,
+        report_hash.clone(),
+        HdkLinkType::Any,
+        ReportTag::tag(),
+    )?;
+    Ok(ReportData {
+        entry_hash: report_hash,
+        report,
+        status: ReportStatus::Open,
+    })
+}
+
+/// Every report we have made with its header hash, oldest first
+fn my_reports() -> ChatResult<Vec<(HeaderHash, Report)>> {
+    let filter = QueryFilter::new()
+        .entry_type(entry_type!(Report)?)
+        .header_type(HeaderType::Create)
+        .include_entries(true);
+    let mut reports = Vec::new();
+    for element in query(filter)? {
+        if let Some(report) = element.entry().to_app_option::<Report>()? {
+            reports.push((element.header_address().clone(), report));
+        }
+    }
+    Ok(reports)
+}
+
+pub(crate) fn resolve_report(input: CloseReportInput) -> ChatResult<ModerationAction> {
+    let CloseReportInput {
+        channel,
+        report_hash,
+        note,
+    } = input;
+    take_action(channel, ModerationKind::ResolveReport(report_hash), note)
+}
+
+pub(crate) fn dismiss_report(input: CloseReportInput) -> ChatResult<ModerationAction> {
+    let CloseReportInput {
+        channel,
+        report_hash,
+        note,
+    } = input;
+    take_action(channel, ModerationKind::DismissReport(report_hash), note)
+}
+
+/// List the reports on a channel we own
+pub(crate) fn list_reports(input: ListReportsInput) -> ChatResult<ReportList> {
+    let ListReportsInput {
+        channel,
+        include_closed,
+    } = input;
+    let me = agent_info()?.agent_initial_pubkey;
+    let links = channel_links(&channel)?;
+    let roles = role_state_from_links(&channel, &links)?;
+    if !roles.roles.has_role(&me, ChannelRole::Owner) {
+        return Err(ChatError::NotAuthorized);
+    }
+
+    // Which reports were closed can be read from the moderation link tags alone
+    let mut statuses = HashMap::new();
+    for (_, kind) in authorized_actions(links, &roles) {
+        match kind {
+            ModerationKind::ResolveReport(report_hash) => {
+                statuses.insert(report_hash, ReportStatus::Resolved);
+            }
+            ModerationKind::DismissReport(report_hash) => {
+                // A report that was resolved stays resolved
+                statuses
+                    .entry(report_hash)
+                    .or_insert(ReportStatus::Dismissed);
+            }
+            _ => (),
+        }
+    }
+
+    // Closed reports are got too, so a report made again after one was closed is still dropped
+    let report_results_input: Vec<GetInput> = get_links(
+        reports_path(&channel)?.path_entry_hash()?,
+        Some(ReportTag::tag()),
+    )?
+    .into_iter()
+    .map(|link| GetInput::new(link.target.into(), GetOptions::default()))
+    .collect();
+    if report_results_input.is_empty() {
+        return Ok(Vec::new().into());
+    }
+    let all_report_results_elements = HDK.with(|hdk| hdk.borrow().get(report_results_input))?;
+
+    let mut reports = Vec::new();
+    for element in all_report_results_elements.into_iter().flatten() {
+        let entry_hash = match element.header().entry_hash() {
+            Some(entry_hash) => entry_hash.clone(),
+            None => continue,
+        };
+        let report = match element.into_inner().1.to_app_option::<Report>() {
+            Ok(Some(report)) => report,
+            // Skip anything in the queue that isn't a report
+            _ => continue,
+        };
+        let status = statuses
+            .get(&entry_hash)
+            .copied()
+            .unwrap_or(ReportStatus::Open);
+        reports.push(ReportData {
+            entry_hash,
+            report,
+            status,
+        });
+    }
+    reports.sort_by_key(|data| data.report.created_at);
+
+    // Only the first report of a message by each reporter counts
+    let mut reported = HashSet::new();
+    reports.retain(|data| {
+        reported.insert((data.report.reporter.clone(), data.report.message.clone()))
+    });
+    if !include_closed.unwrap_or(false) {
+        reports.retain(|data| data.status == ReportStatus::Open);
+    }
+    Ok(reports.into())
+}
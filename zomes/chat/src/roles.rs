@@ -0,0 +1,325 @@
+//! Who has authority over a channel.
+//!
+//! The owner is the agent the channel's uuid names, see [`Channel::is_owned_by`],
+//! and validation only lets them link the channel's infos. Owners grant and revoke admins
+//! and moderators, and admins grant and revoke moderators.
+//!
+//! Grants and revocations are links from the channel carrying a [`RoleTag`].
+//! Validation checks that the grantor held the needed role when the link was made.
+//! It can't see later revocations, so it still takes a revoked grant as authority.
+//! Readers replay the links in order, so a revocation takes effect for them at once,
+//! and drop any link made by an agent who had lost their role by then.
+use crate::{
+    channel::{Channel, ChannelInfoTag},
+    error::{ChatError, ChatResult},
+};
+use hdk::prelude::*;
+use std::collections::HashMap;
+
+/// A role on a channel, ordered from least to most authority
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ChannelRole {
+    Moderator,
+    Admin,
+    Owner,
+}
+
+impl ChannelRole {
+    /// The role needed to grant or revoke this one.
+    /// Ownership can't be granted.
+    pub fn granted_by(&self) -> Option<ChannelRole> {
+        match self {
+            ChannelRole::Moderator => Some(ChannelRole::Admin),
+            ChannelRole::Admin => Some(ChannelRole::Owner),
+            ChannelRole::Owner => None,
+        }
+    }
+}
+
+/// Input to the grant role and revoke role calls
+#[derive(Debug, Serialize, Deserialize, SerializedBytes)]
+pub struct RoleInput {
+    pub channel: Channel,
+    pub agent: AgentPubKey,
+    pub role: ChannelRole,
+}
+
+/// Everyone with a role on a channel
+#[derive(Debug, Serialize, Deserialize, SerializedBytes, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ChannelRoles {
+    pub owner: Option<AgentPubKey>,
+    pub admins: Vec<AgentPubKey>,
+    pub moderators: Vec<AgentPubKey>,
+}
+
+impl ChannelRoles {
+    pub fn role_of(&self, agent: &AgentPubKey) -> Option<ChannelRole> {
+        if self.owner.as_ref() == Some(agent) {
+            Some(ChannelRole::Owner)
+        } else if self.admins.contains(agent) {
+            Some(ChannelRole::Admin)
+        } else if self.moderators.contains(agent) {
+            Some(ChannelRole::Moderator)
+        } else {
+            None
+        }
+    }
+
+    /// Whether `agent` has `role` or a role above it
+    pub fn has_role(&self, agent: &AgentPubKey, role: ChannelRole) -> bool {
+        self.role_of(agent).map_or(false, |held| held >= role)
+    }
+}
+
+/// What a role link records.
+/// `authority` is the header that gave the grantor their role: their channel info link
+/// if they own the channel, otherwise the link that granted them their role.
+#[derive(Debug, Clone, Serialize, Deserialize, SerializedBytes, PartialEq, Eq)]
+pub(crate) struct RoleTag {
+    pub(crate) role: ChannelRole,
+    /// `false` for a revocation
+    pub(crate) granted: bool,
+    pub(crate) authority: HeaderHash,
+}
+
+impl RoleTag {
+    const TAG: &'static [u8; 4] = b"role";
+
+    /// The prefix every role tag starts with
+    pub(crate) fn any() -> LinkTag {
+        LinkTag::new(*Self::TAG)
+    }
+
+    pub(crate) fn tag(&self) -> ChatResult<LinkTag> {
+        let bytes: Vec<u8> = UnsafeBytes::from(SerializedBytes::try_from(self.clone())?).into();
+        Ok(LinkTag::new([&Self::TAG[..], &bytes].concat()))
+    }
+
+    /// The role tag in a link tag, or `None` for any other tag
+    pub(crate) fn parse(tag: &LinkTag) -> Option<Self> {
+        let bytes = tag.0.strip_prefix(&Self::TAG[..])?;
+        Self::try_from(SerializedBytes::from(UnsafeBytes::from(bytes.to_vec()))).ok()
+    }
+}
+
+/// The role an agent holds and the header to cite as their authority
+type Authority = (ChannelRole, HeaderHash);
+
+/// The roles on a channel, with the header that gave each agent theirs
+pub(crate) struct RoleState {
+    pub(crate) roles: ChannelRoles,
+    authorities: HashMap<AgentPubKey, Authority>,
+    /// When the owner linked their first info
+    owner_since: Option<Timestamp>,
+    /// Every change to each agent's role, oldest first. `None` for a revocation.
+    history: HashMap<AgentPubKey, Vec<(Timestamp, Option<Authority>)>>,
+}
+
+impl RoleState {
+    /// The role `agent` holds and the header to cite as their authority
+    pub(crate) fn authority(&self, agent: &AgentPubKey) -> Option<Authority> {
+        self.authorities.get(agent).cloned()
+    }
+
+    /// The role `agent` held at `at`
+    pub(crate) fn role_at(&self, agent: &AgentPubKey, at: Timestamp) -> Option<ChannelRole> {
+        if self.roles.owner.as_ref() == Some(agent) {
+            return self
+                .owner_since
+                .filter(|since| *since <= at)
+                .map(|_| ChannelRole::Owner);
+        }
+        let changes = self.history.get(agent)?;
+        let (_, authority) = changes.iter().take_while(|(t, _)| *t <= at).last()?;
+        authority.as_ref().map(|(role, _)| *role)
+    }
+}
+
+/// A role link as read back from the DHT
+struct RoleLink {
+    author: AgentPubKey,
+    timestamp: Timestamp,
+    agent: EntryHash,
+    header_hash: HeaderHash,
+    tag: RoleTag,
+}
+
+/// Every link from a channel's path, with the header hash of each.
+/// Read once and shared by everything that replays the links against the channel's roles.
+pub(crate) fn channel_links(channel: &Channel) -> ChatResult<Vec<(HeaderHash, CreateLink)>> {
+    Ok(channels_links(std::slice::from_ref(channel))?
+        .pop()
+        .unwrap_or_default())
+}
+
+/// The links from several channels' paths in one batch, in the order of the channels
+pub(crate) fn channels_links(
+    channels: &[Channel],
+) -> ChatResult<Vec<Vec<(HeaderHash, CreateLink)>>> {
+    let inputs = channels
+        .iter()
+        .map(|channel| {
+            let path: Path = channel.clone().try_into()?;
+            Ok(GetLinksInput::new(path.path_entry_hash()?, None))
+        })
+        .collect::<ChatResult<Vec<_>>>()?;
+    if inputs.is_empty() {
+        return Ok(Vec::new());
+    }
+    let all_details = HDK.with(|hdk| hdk.borrow().get_link_details(inputs))?;
+    Ok(all_details
+        .into_iter()
+        .map(|details| {
+            details
+                .into_inner()
+                .into_iter()
+                .filter_map(|(create, _deletes)| match create.header() {
+                    Header::CreateLink(create_link) => {
+                        Some((create.header_address().clone(), create_link.clone()))
+                    }
+                    _ => None,
+                })
+                .collect()
+        })
+        .collect())
+}
+
+/// Replay the channel info and role links on a channel to find out who holds which role
+pub(crate) fn get_role_state(channel: &Channel) -> ChatResult<RoleState> {
+    role_state_from_links(channel, &channel_links(channel)?)
+}
+
+/// Replay the channel info and role links among a channel's links
+pub(crate) fn role_state_from_links(
+    channel: &Channel,
+    links: &[(HeaderHash, CreateLink)],
+) -> ChatResult<RoleState> {
+    let mut owner: Option<(Timestamp, AgentPubKey, HeaderHash)> = None;
+    let mut role_links = Vec::new();
+    for (header_hash, create_link) in links {
+        if create_link.tag == ChannelInfoTag::tag() && channel.is_owned_by(&create_link.author) {
+            // The owner's earliest info link is their authority
+            let earlier = owner
+                .as_ref()
+                .map_or(true, |(timestamp, _, _)| create_link.timestamp < *timestamp);
+            if earlier {
+                owner = Some((
+                    create_link.timestamp,
+                    create_link.author.clone(),
+                    header_hash.clone(),
+                ));
+            }
+        } else if let Some(tag) = RoleTag::parse(&create_link.tag) {
+            role_links.push(RoleLink {
+                author: create_link.author.clone(),
+                timestamp: create_link.timestamp,
+                agent: create_link.target_address.clone(),
+                header_hash: header_hash.clone(),
+                tag,
+            });
+        }
+    }
+
+    let mut authorities: HashMap<AgentPubKey, Authority> = HashMap::new();
+    let mut history: HashMap<AgentPubKey, Vec<(Timestamp, Option<Authority>)>> = HashMap::new();
+    let owner_since = owner.as_ref().map(|(timestamp, _, _)| *timestamp);
+    let owner = owner.map(|(_, owner, header_hash)| {
+        authorities.insert(owner.clone(), (ChannelRole::Owner, header_hash));
+        owner
+    });
+
+    role_links.sort_by_key(|link| link.timestamp);
+    for link in role_links {
+        let needed = match link.tag.role.granted_by() {
+            Some(needed) => needed,
+            None => continue,
+        };
+        let agent = AgentPubKey::from_raw_36(link.agent.get_raw_36().to_vec());
+        let grantor_role = authorities.get(&link.author).map(|(role, _)| *role);
+        let agent_role = authorities.get(&agent).map(|(role, _)| *role);
+        // Nobody can change the role of someone at or above their own
+        let allowed = match grantor_role {
+            Some(grantor_role) => {
+                grantor_role >= needed && agent_role.map_or(true, |role| role < grantor_role)
+            }
+            None => false,
+        };
+        if !allowed {
+            continue;
+        }
+        if link.tag.granted {
+            let authority = (link.tag.role, link.header_hash);
+            authorities.insert(agent.clone(), authority.clone());
+            history
+                .entry(agent)
+                .or_default()
+                .push((link.timestamp, Some(authority)));
+        } else if agent_role == Some(link.tag.role) {
+            authorities.remove(&agent);
+            history
+                .entry(agent)
+                .or_default()
+                .push((link.timestamp, None));
+        }
+    }
+
+    let mut roles = ChannelRoles {
+        owner,
+        ..Default::default()
+    };
+    for (agent, (role, _)) in authorities.iter() {
+        match role {
+            ChannelRole::Admin => roles.admins.push(agent.clone()),
+            ChannelRole::Moderator => roles.moderators.push(agent.clone()),
+            ChannelRole::Owner => (),
+        }
+    }
+    Ok(RoleState {
+        roles,
+        authorities,
+        owner_since,
+        history,
+    })
+}
+
+pub(crate) fn get_channel_roles(channel: &Channel) -> ChatResult<ChannelRoles> {
+    Ok(get_role_state(channel)?.roles)
+}
+
+pub(crate) fn grant_role(input: RoleInput) -> ChatResult<()> {
+    change_role(input, true)
+}
+
+pub(crate) fn revoke_role(input: RoleInput) -> ChatResult<()> {
+    change_role(input, false)
+}
+
+fn change_role(input: RoleInput, granted: bool) -> ChatResult<()> {
+    let RoleInput {
+        channel,
+        agent,
+        role,
+    } = input;
+    let needed = role.granted_by().ok_or(ChatError::NotAuthorized)?;
+    let state = get_role_state(&channel)?;
+    let me = agent_info()?.agent_initial_pubkey;
+    let (my_role, authority) = state.authority(&me).ok_or(ChatError::NotAuthorized)?;
+    let agent_role = state.roles.role_of(&agent);
+    if my_role < needed || agent_role.map_or(false, |role| role >= my_role) {
+        return Err(ChatError::NotAuthorized);
+    }
+    let path: Path = channel.try_into()?;
+    let tag = RoleTag {
+        role,
+        granted,
+        authority,
+    };
+    create_link(
+        path.path_entry_hash()?,
+        agent.into(),
+        HdkLinkType::Any,
+        tag.tag()?,
+    )?;
+    Ok(())
+}
@@ -0,0 +1,462 @@
+//! Sending signals to other agents, and checking the signals they send us.
+//!
+//! Signals fan out in chunks to a bounded audience, optionally relayed through
+//! the recipients so no single agent has to reach everyone.
+//!
+//! `recv_remote_signal` is callable by anyone, so every payload is checked for
+//! authorship, freshness, the receiver's channel interest and block list, and each sender is rate limited.
+//! Message signals carry the sender's signature over the message and the channel it is on,
+//! and the channel's info must be on the DHT.
+//! The rate limit keeps a private receipt on our chain for every signal we pass on,
+//! so it holds across zome calls.
+use crate::{
+    blocking::get_block_list,
+    channel::{Channel, ChannelData},
+    error::ChatResult,
+    message::{SigResults, SignalMessageData},
+    utils::recent_elements,
+    validation::validate_message,
+    SignalPayload,
+};
+use hdk::prelude::*;
+
+/// How long after a message was created we still accept a signal for it
+pub const SIGNAL_MAX_AGE_SECONDS: i64 = 5 * 60;
+/// How far in the future a signalled message may claim to be created
+pub const SIGNAL_MAX_CLOCK_SKEW_SECONDS: i64 = 60;
+/// Length of the sliding rate limiting window
+pub const SIGNAL_RATE_WINDOW_SECONDS: i64 = 60;
+/// Signals accepted from a single sender per window
+pub const SIGNAL_RATE_LIMIT: usize = 60;
+/// How far back on our chain receipts are counted.
+/// Receipts from other senders can push a sender's older ones out of this,
+/// so under heavy traffic a sender may get a few more signals through than the limit.
+pub const SIGNAL_RECEIPT_WINDOW_HEADERS: u32 = 1000;
+
+/// Signal fan-out limits, read from the DNA properties.
+/// Missing properties fall back to the defaults, malformed ones are an error.
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, PartialEq, Eq)]
+pub struct SignalConfig {
+    /// The most agents a single signal is sent to
+    #[serde(default = "SignalConfig::default_max_audience")]
+    pub max_signal_audience: usize,
+    /// How many agents are passed to each `remote_signal` call
+    #[serde(default = "SignalConfig::default_chunk_size")]
+    pub signal_chunk_size: usize,
+    /// How many agents each agent forwards to in relay mode
+    #[serde(default = "SignalConfig::default_relay_fanout")]
+    pub signal_relay_fanout: usize,
+}
+
+impl SignalConfig {
+    fn default_max_audience() -> usize {
+        1000
+    }
+    fn default_chunk_size() -> usize {
+        50
+    }
+    fn default_relay_fanout() -> usize {
+        8
+    }
+
+    /// A DNA without properties gets the defaults. Properties that don't parse are an error
+    /// rather than quietly lifting the limits.
+    pub fn load() -> ChatResult<Self> {
+        let properties = dna_info()?.properties;
+        if matches!(properties.bytes().as_slice(), [] | [0xc0]) {
+            return Ok(Self::default());
+        }
+        Self::try_from(properties).map_err(|e| {
+            WasmError::Guest(format!("The signal DNA properties are malformed: {:?}", e)).into()
+        })
+    }
+}
+
+impl Default for SignalConfig {
+    fn default() -> Self {
+        Self {
+            max_signal_audience: Self::default_max_audience(),
+            signal_chunk_size: Self::default_chunk_size(),
+            signal_relay_fanout: Self::default_relay_fanout(),
+        }
+    }
+}
+
+/// How a signal call fans out and what it reports back
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, Default, PartialEq, Eq)]
+pub struct SignalOptions {
+    /// List every recipient in `SigResults.sent` instead of just counting them
+    pub detailed: Option<bool>,
+    /// Only signal a few agents directly and have them forward to the rest
+    pub relay: Option<bool>,
+}
+
+/// A signal forwarded on behalf of its origin.
+/// The origin signs the payload together with the audience it sent it to,
+/// so relays can neither alter the payload nor forward it to anyone the origin didn't choose.
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RelaySignal {
+    pub origin: AgentPubKey,
+    pub signature: Signature,
+    pub payload: Box<SignalPayload>,
+    /// The group of agents the origin sent this signal to
+    pub audience: Vec<AgentPubKey>,
+    /// The agents this recipient is responsible for forwarding to, all in `audience`
+    pub forward_to: Vec<AgentPubKey>,
+}
+
+/// The channels this agent wants signals for, saved privately on the source chain.
+/// `None` means every channel.
+#[hdk_entry(id = "signal_filter", visibility = "private")]
+#[derive(Clone, PartialEq, Eq, Default)]
+pub struct SignalFilter {
+    pub channels: Option<Vec<Channel>>,
+}
+
+impl SignalFilter {
+    pub fn wants(&self, channel: &Channel) -> bool {
+        match &self.channels {
+            Some(channels) => channels.contains(channel),
+            None => true,
+        }
+    }
+}
+
+/// A signal we passed on to the UI, saved privately so the rate limit outlives the zome call
+#[hdk_entry(id = "signal_receipt", visibility = "private")]
+#[derive(Clone, PartialEq, Eq)]
+pub struct SignalReceipt {
+    pub sender: AgentPubKey,
+    pub received_at: Timestamp,
+}
+
+pub(crate) fn set_signal_filter(filter: SignalFilter) -> ChatResult<()> {
+    create_entry(&filter)?;
+    Ok(())
+}
+
+/// The latest filter on our chain
+pub(crate) fn get_signal_filter() -> ChatResult<SignalFilter> {
+    let filter = QueryFilter::new()
+        .entry_type(entry_type!(SignalFilter)?)
+        .include_entries(true);
+    let latest = query(filter)?
+        .into_iter()
+        .max_by_key(|element| element.header().header_seq());
+    match latest {
+        Some(element) => Ok(element
+            .entry()
+            .to_app_option::<SignalFilter>()?
+            .unwrap_or_default()),
+        None => Ok(SignalFilter::default()),
+    }
+}
+
+/// Send a payload to `recipients`, bounded by the configured audience.
+/// A larger audience is sampled at random, so the same agents aren't always left out.
+pub(crate) fn fan_out(
+    payload: SignalPayload,
+    recipients: Vec<AgentPubKey>,
+    total: usize,
+    options: &SignalOptions,
+) -> ChatResult<SigResults> {
+    let config = SignalConfig::load()?;
+    let recipients = sample(recipients, config.max_signal_audience)?;
+    let payload = sign_payload(payload)?;
+    debug!("sending to {} agents", recipients.len());
+
+    if options.relay.unwrap_or(false) {
+        relay_from_origin(payload, &recipients, &config)?;
+    } else {
+        send_in_chunks(payload, &recipients, &config)?;
+    }
+
+    let sent = if options.detailed.unwrap_or(false) {
+        Some(recipients.iter().map(|a| a.to_string()).collect())
+    } else {
+        None
+    };
+    Ok(SigResults {
+        total,
+        sent_count: recipients.len(),
+        sent,
+    })
+}
+
+/// Sign a message signal's message and channel data as ourselves
+fn sign_payload(payload: SignalPayload) -> ChatResult<SignalPayload> {
+    match payload {
+        SignalPayload::Message(mut data) => {
+            let me = agent_info()?.agent_latest_pubkey;
+            data.signature = Some(sign(me, (&data.message_data, &data.channel_data))?);
+            Ok(SignalPayload::Message(data))
+        }
+        payload => Ok(payload),
+    }
+}
+
+fn send_in_chunks(
+    payload: SignalPayload,
+    recipients: &[AgentPubKey],
+    config: &SignalConfig,
+) -> ChatResult<()> {
+    if recipients.is_empty() {
+        return Ok(());
+    }
+    let payload = ExternIO::encode(payload)?;
+    for chunk in recipients.chunks(config.signal_chunk_size.max(1)) {
+        remote_signal(payload.clone(), chunk.to_vec())?;
+    }
+    Ok(())
+}
+
+/// Up to `max` of `agents`, picked at random
+fn sample(mut agents: Vec<AgentPubKey>, max: usize) -> ChatResult<Vec<AgentPubKey>> {
+    if agents.len() <= max {
+        return Ok(agents);
+    }
+    // A partial Fisher-Yates shuffle, moving the picked agents to the front
+    let random = random_bytes((max * 4) as u32)?;
+    for (i, bytes) in random.chunks_exact(4).enumerate() {
+        let r = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize;
+        agents.swap(i, i + r % (agents.len() - i));
+    }
+    agents.truncate(max);
+    Ok(agents)
+}
+
+/// Split `recipients` into at most `signal_relay_fanout` groups
+fn relay_groups<'a>(
+    recipients: &'a [AgentPubKey],
+    config: &SignalConfig,
+) -> impl Iterator<Item = &'a [AgentPubKey]> {
+    let fanout = config.signal_relay_fanout.max(1);
+    let group_size = ((recipients.len() + fanout - 1) / fanout).max(1);
+    recipients.chunks(group_size)
+}
+
+/// Relay a payload of our own, signing it for each group.
+/// The first agent of each group is signalled and forwards to the rest of its group.
+fn relay_from_origin(
+    payload: SignalPayload,
+    recipients: &[AgentPubKey],
+    config: &SignalConfig,
+) -> ChatResult<()> {
+    let origin = agent_info()?.agent_latest_pubkey;
+    for group in relay_groups(recipients, config) {
+        let audience = group.to_vec();
+        let signature = sign(origin.clone(), (&payload, &audience))?;
+        send_relay(
+            RelaySignal {
+                origin: origin.clone(),
+                signature,
+                payload: Box::new(payload.clone()),
+                audience,
+                forward_to: group[1..].to_vec(),
+            },
+            &group[0],
+        )?;
+    }
+    Ok(())
+}
+
+/// Pass a relayed signal on to the agents we are responsible for, in the same way
+fn forward(signal: &RelaySignal, config: &SignalConfig) -> ChatResult<()> {
+    for group in relay_groups(&signal.forward_to, config) {
+        send_relay(
+            RelaySignal {
+                forward_to: group[1..].to_vec(),
+                ..signal.clone()
+            },
+            &group[0],
+        )?;
+    }
+    Ok(())
+}
+
+fn send_relay(signal: RelaySignal, to: &AgentPubKey) -> ChatResult<()> {
+    let payload = ExternIO::encode(SignalPayload::Relay(signal))?;
+    remote_signal(payload, vec![to.clone()])?;
+    Ok(())
+}
+
+/// Handle a signal from `sender`, returning the payload to pass on to the UI, if any.
+/// Relayed signals are checked for authorship and age before being forwarded,
+/// and forwarded whether or not we want them ourselves.
+pub(crate) fn receive_remote_signal(
+    sender: &AgentPubKey,
+    signal: SignalPayload,
+) -> ChatResult<Option<SignalPayload>> {
+    match signal {
+        SignalPayload::Relay(relay_signal) => {
+            if !is_authentic_relay(&relay_signal)? {
+                debug!(
+                    "Dropping relayed signal that doesn't check out from {:?}",
+                    sender
+                );
+                return Ok(None);
+            }
+            let origin = &relay_signal.origin;
+            let channel = match genuine_channel(origin, &relay_signal.payload)? {
+                Some(channel) => channel.clone(),
+                None => return Ok(None),
+            };
+            forward(&relay_signal, &SignalConfig::load()?)?;
+            if is_wanted(origin, &channel)? && within_rate_limit(origin)? {
+                Ok(Some(*relay_signal.payload))
+            } else {
+                Ok(None)
+            }
+        }
+        signal => {
+            let wanted = match genuine_channel(sender, &signal)? {
+                Some(channel) => is_wanted(sender, channel)? && within_rate_limit(sender)?,
+                None => false,
+            };
+            if wanted {
+                Ok(Some(signal))
+            } else {
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// A relayed signal must be signed by its origin over its payload and audience,
+/// include us in the audience and only have us forward to others in it
+fn is_authentic_relay(relay_signal: &RelaySignal) -> ChatResult<bool> {
+    let RelaySignal {
+        origin,
+        signature,
+        payload,
+        audience,
+        forward_to,
+    } = relay_signal;
+    // A relay can't be wrapped in another relay
+    if matches!(payload.as_ref(), SignalPayload::Relay(_)) {
+        return Ok(false);
+    }
+    let me = agent_info()?.agent_latest_pubkey;
+    if !audience.contains(&me) || forward_to.iter().any(|agent| !audience.contains(agent)) {
+        return Ok(false);
+    }
+    Ok(verify_signature(
+        origin.clone(),
+        signature.clone(),
+        (payload.as_ref(), audience),
+    )?)
+}
+
+/// The channel a signal from `sender` is about, or `None` if the signal isn't genuine:
+/// not authored by the sender, or too old or too far in the future
+fn genuine_channel<'a>(
+    sender: &AgentPubKey,
+    signal: &'a SignalPayload,
+) -> ChatResult<Option<&'a Channel>> {
+    let channel = match signal {
+        SignalPayload::Message(data) => {
+            if !is_authentic_message(sender, data)? {
+                debug!("Dropping message signal not authored by {:?}", sender);
+                return Ok(None);
+            }
+            if !is_genuine_channel_data(&data.channel_data)? {
+                debug!("Dropping message signal with made up channel data");
+                return Ok(None);
+            }
+            &data.channel_data.entry
+        }
+        SignalPayload::Channel(data) => {
+            if data.info.created_by != *sender {
+                debug!("Dropping channel signal not authored by {:?}", sender);
+                return Ok(None);
+            }
+            &data.entry
+        }
+        SignalPayload::Relay(_) => return Ok(None),
+    };
+    Ok(Some(channel))
+}
+
+/// Whether a genuine signal from `sender` on `channel` should be passed on to the UI
+fn is_wanted(sender: &AgentPubKey, channel: &Channel) -> ChatResult<bool> {
+    if get_block_list()?.is_blocked(sender) {
+        return Ok(false);
+    }
+    get_signal_filter().map(|filter| filter.wants(channel))
+}
+
+/// A message signal is only accepted from the message's author, signed by them,
+/// with content matching its hash and a plausible creation time
+fn is_authentic_message(sender: &AgentPubKey, data: &SignalMessageData) -> ChatResult<bool> {
+    let message_data = &data.message_data;
+    if message_data.created_by != *sender {
+        return Ok(false);
+    }
+    let signature = match &data.signature {
+        Some(signature) => signature.clone(),
+        None => return Ok(false),
+    };
+    if !verify_signature(
+        sender.clone(),
+        signature,
+        (message_data, &data.channel_data),
+    )? {
+        return Ok(false);
+    }
+    if validate_message(&message_data.entry) != ValidateCallbackResult::Valid {
+        return Ok(false);
+    }
+    if hash_entry(&message_data.entry)? != message_data.entry_hash {
+        return Ok(false);
+    }
+    let age = match sys_time()?
+        .as_micros()
+        .checked_sub(message_data.created_at.as_micros())
+    {
+        Some(age) => age,
+        None => return Ok(false),
+    };
+    Ok(age <= SIGNAL_MAX_AGE_SECONDS * 1_000_000
+        && age >= -SIGNAL_MAX_CLOCK_SKEW_SECONDS * 1_000_000)
+}
+
+/// The channel data of a message signal must be a channel info that is on the DHT,
+/// for the channel it names
+fn is_genuine_channel_data(channel_data: &ChannelData) -> ChatResult<bool> {
+    let ChannelData { entry, info, .. } = channel_data;
+    if entry.category != info.category || entry.uuid != info.uuid {
+        return Ok(false);
+    }
+    Ok(get(hash_entry(info)?, GetOptions::default())?.is_some())
+}
+
+/// Whether `sender` is still under their rate limit, counting this signal if so.
+/// The window slides, counting the receipts of the last `SIGNAL_RATE_WINDOW_SECONDS`.
+fn within_rate_limit(sender: &AgentPubKey) -> ChatResult<bool> {
+    let now = sys_time()?;
+    let window_start = now.as_micros() - SIGNAL_RATE_WINDOW_SECONDS * 1_000_000;
+    let receipts = recent_elements(
+        SIGNAL_RECEIPT_WINDOW_HEADERS,
+        QueryFilter::new()
+            .entry_type(entry_type!(SignalReceipt)?)
+            .include_entries(true),
+    )?;
+    let mut received = 0;
+    for element in receipts.iter() {
+        if let Some(receipt) = element.entry().to_app_option::<SignalReceipt>()? {
+            if receipt.sender == *sender && receipt.received_at.as_micros() > window_start {
+                received += 1;
+            }
+        }
+    }
+    if received >= SIGNAL_RATE_LIMIT {
+        debug!("Rate limiting signals from {:?}", sender);
+        return Ok(false);
+    }
+    create_entry(&SignalReceipt {
+        sender: sender.clone(),
+        received_at: now,
+    })?;
+    Ok(true)
+}
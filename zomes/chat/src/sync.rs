@@ -0,0 +1,69 @@
+//! Catching up on everything that happened while we were away.
+use crate::{
+    channel::{handlers::find_channels, ChannelData, ChannelListInput},
+    error::ChatResult,
+    message::{
+        handlers::{list_messages_since, presence_since},
+        PresenceChanges,
+    },
+    ChannelMessages,
+};
+use hdk::prelude::*;
+
+/// Input to the sync since call
+#[derive(Debug, Serialize, Deserialize, SerializedBytes)]
+pub struct SyncInput {
+    pub since: Timestamp,
+    pub category: String,
+}
+
+/// Everything in a category that changed since the requested time
+#[derive(Debug, Serialize, Deserialize, SerializedBytes, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncResult {
+    /// Channels created, renamed or archived since then
+    pub channels: Vec<ChannelData>,
+    /// New messages, only for channels that have any
+    pub messages: Vec<ChannelMessages>,
+    pub presence: PresenceChanges,
+    /// Pass this as `since` on the next sync
+    pub synced_at: Timestamp,
+}
+
+pub(crate) fn sync_since(input: SyncInput) -> ChatResult<SyncResult> {
+    let SyncInput { since, category } = input;
+    // Read the time first so nothing that happens during the sync is missed next time
+    let synced_at = sys_time()?;
+
+    let all_channels = find_channels(ChannelListInput {
+        category: Some(category),
+        include_archived: Some(true),
+        ..Default::default()
+    })?
+    .channels;
+
+    let mut channels = Vec::new();
+    let mut messages = Vec::new();
+    for channel in all_channels {
+        if channel.info.created_at > since {
+            channels.push(channel.clone());
+        }
+        if channel.info.archived {
+            continue;
+        }
+        let new_messages = list_messages_since(channel.entry.clone(), since)?;
+        if !new_messages.is_empty() {
+            messages.push(ChannelMessages {
+                channel,
+                messages: new_messages,
+            });
+        }
+    }
+
+    Ok(SyncResult {
+        channels,
+        messages,
+        presence: presence_since(since)?,
+        synced_at,
+    })
+}
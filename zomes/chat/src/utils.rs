@@ -5,24 +5,27 @@ use crate::error::ChatResult;
 
 /// Get a local header from your chain
 pub(crate) fn get_local_header(header_hash: &HeaderHash) -> ChatResult<Option<Header>> {
-    // Get the latest chain header
-    // Query iterates backwards so index 0 is the latest.
-    let header = query(QueryFilter::new())?.into_iter().find_map(|el| {
-        if el.header_address() == header_hash {
-            Some(
-                el.into_inner()
-                    .0
-                    .into_header_and_signature()
-                    .0
-                    .into_content(),
-            )
-        } else {
-            None
-        }
+    // A get on our own header is answered locally, without scanning the chain for it
+    let header = get(header_hash.clone(), GetOptions::content())?.map(|el| {
+        el.into_inner()
+            .0
+            .into_header_and_signature()
+            .0
+            .into_content()
     });
     Ok(header)
 }
 
+/// The elements in the last `count` headers of our chain that pass `filter`
+pub(crate) fn recent_elements(count: u32, filter: QueryFilter) -> ChatResult<Vec<Element>> {
+    let (_, head_seq, _) = agent_info()?.chain_head;
+    let filter = filter.sequence_range(ChainQueryFilterRange::HeaderSeqRange(
+        head_seq.saturating_sub(count),
+        head_seq,
+    ));
+    Ok(query(filter)?)
+}
+
 /// Turns a unix timestamp into a Date
 pub(crate) fn to_date(timestamp: Timestamp) -> chrono::DateTime<chrono::Utc> {
     timestamp.try_into().unwrap()
@@ -1,20 +1,931 @@
-use crate::message::Message;
+use crate::{
+    batching_helper::timestamp_into_path,
+    category::{handlers::categories_path, CategoryInfo, CategoryTag},
+    channel::{
+        handlers::category_path, is_owner_uuid, Channel, ChannelIndexTag, ChannelInfo,
+        ChannelInfoTag, ChannelSummary,
+    },
+    membership::MembershipTag,
+    message::{LastSeenKey, Message},
+    moderation::{ModerationAction, ModerationKind, ModerationTag, MAX_MODERATION_REASON_LENGTH},
+    rate_limit::{check_rate_limit, RateLimitConfig, MAX_SLOW_MODE_SECONDS},
+    reports::{reports_path, Report, ReportTag, MAX_REPORT_REASON_LENGTH},
+    roles::{ChannelRole, RoleTag},
+    ChatError,
+};
 use hdk::prelude::*;
+use uuid::Uuid;
 
-pub fn __validate_create_entry(entry: Entry) -> ExternResult<ValidateCallbackResult> {
+/// Longest message content (in bytes) we accept
+pub const MAX_MESSAGE_LENGTH: usize = 1024;
+/// Longest channel name (in characters) we accept
+pub const MAX_CHANNEL_NAME_LENGTH: usize = 50;
+/// Longest category (in bytes) we accept
+pub const MAX_CATEGORY_LENGTH: usize = 64;
+/// Longest category name (in characters) we accept
+pub const MAX_CATEGORY_NAME_LENGTH: usize = 50;
+/// How far the `created_at` of a channel info may drift from its header timestamp
+pub const CHANNEL_INFO_TIMESTAMP_TOLERANCE_SECONDS: i64 = 60;
+/// How far the `created_at` of a category info may drift from its header timestamp
+pub const CATEGORY_INFO_TIMESTAMP_TOLERANCE_SECONDS: i64 = 60;
+/// How far the `created_at` of a moderation action may drift from its header timestamp
+pub const MODERATION_ACTION_TIMESTAMP_TOLERANCE_SECONDS: i64 = 60;
+/// How far the `created_at` of a report may drift from its header timestamp
+pub const REPORT_TIMESTAMP_TOLERANCE_SECONDS: i64 = 60;
+/// How far back an author's chain the read-only check looks for their last chat or membership link
+pub const READ_ONLY_CHECK_WINDOW_HEADERS: u32 = 100;
+
+pub fn __validate_create_entry(
+    header: &EntryCreationHeader,
+    entry: Entry,
+) -> ExternResult<ValidateCallbackResult> {
     match entry {
-        Entry::App(_) => match entry.try_into() {
-            Ok(Message { content, .. }) => {
-                if content.len() <= 1024 {
-                    Ok(ValidateCallbackResult::Valid)
-                } else {
-                    Ok(ValidateCallbackResult::Invalid(
-                        "Message too long".to_string(),
+        Entry::App(_) => {
+            // An entry is checked against every type it deserializes as,
+            // so a crafted entry can't pass as a message and be read as a channel info.
+            let mut is_chat_entry = false;
+            if let Ok(message) = Message::try_from(&entry) {
+                let result = validate_message(&message);
+                if result != ValidateCallbackResult::Valid {
+                    return Ok(result);
+                }
+                is_chat_entry = true;
+            }
+            if let Ok(info) = ChannelInfo::try_from(&entry) {
+                let result = validate_channel_info(&info, header.author(), *header.timestamp());
+                if result != ValidateCallbackResult::Valid {
+                    return Ok(result);
+                }
+                is_chat_entry = true;
+            }
+            if let Ok(info) = CategoryInfo::try_from(&entry) {
+                let result = validate_category_info(&info, header.author(), *header.timestamp());
+                if result != ValidateCallbackResult::Valid {
+                    return Ok(result);
+                }
+                is_chat_entry = true;
+            }
+            if let Ok(action) = ModerationAction::try_from(&entry) {
+                let result =
+                    validate_moderation_action(&action, header.author(), *header.timestamp())?;
+                if result != ValidateCallbackResult::Valid {
+                    return Ok(result);
+                }
+                is_chat_entry = true;
+            }
+            if let Ok(report) = Report::try_from(&entry) {
+                let result = validate_report(&report, header.author(), *header.timestamp())?;
+                if result != ValidateCallbackResult::Valid {
+                    return Ok(result);
+                }
+                is_chat_entry = true;
+            }
+            if is_chat_entry {
+                validate_not_read_only(header.prev_header())
+            } else {
+                Ok(ValidateCallbackResult::Valid)
+            }
+        }
+        _ => Ok(ValidateCallbackResult::Valid),
+    }
+}
+
+/// Element authorities check chat entries and links the same way their own authorities do,
+/// so `must_get_valid_element` on one of them also means it passed these checks
+pub fn __validate_store_element(element: &Element) -> ExternResult<ValidateCallbackResult> {
+    let header = match element.header() {
+        Header::CreateLink(create_link) => return __validate_create_link(create_link),
+        Header::Create(create) => EntryCreationHeader::Create(create.clone()),
+        Header::Update(update) => {
+            let original = must_get_header(update.original_header_address.clone())?;
+            let result = validate_original_author(&update.author, original.header().author());
+            if result != ValidateCallbackResult::Valid {
+                return Ok(result);
+            }
+            EntryCreationHeader::Update(update.clone())
+        }
+        Header::Delete(delete) => {
+            let original = must_get_header(delete.deletes_address.clone())?;
+            return Ok(validate_original_author(
+                &delete.author,
+                original.header().author(),
+            ));
+        }
+        Header::DeleteLink(delete_link) => {
+            let original = must_get_header(delete_link.link_add_address.clone())?;
+            return Ok(validate_original_author(
+                &delete_link.author,
+                original.header().author(),
+            ));
+        }
+        _ => return Ok(ValidateCallbackResult::Valid),
+    };
+    match element.entry().as_option() {
+        Some(entry) => __validate_create_entry(&header, entry.clone()),
+        // Private entries aren't chat data
+        None => Ok(ValidateCallbackResult::Valid),
+    }
+}
+
+/// Only whoever created an entry or link may update or delete it,
+/// so nobody can take down another agent's channel infos, index, role, moderation or report links
+pub fn validate_original_author(
+    author: &AgentPubKey,
+    original_author: &AgentPubKey,
+) -> ValidateCallbackResult {
+    if author == original_author {
+        ValidateCallbackResult::Valid
+    } else {
+        ValidateCallbackResult::Invalid(
+            "Only the author can update or delete their own entries and links".to_string(),
+        )
+    }
+}
+
+/// Whether `created_at` is within `tolerance_seconds` of the header timestamp.
+/// Timestamps so far apart that the difference overflows are not.
+fn within_tolerance(
+    created_at: Timestamp,
+    header_timestamp: Timestamp,
+    tolerance_seconds: i64,
+) -> bool {
+    created_at
+        .as_micros()
+        .checked_sub(header_timestamp.as_micros())
+        .and_then(i64::checked_abs)
+        .map_or(false, |drift| drift <= tolerance_seconds * 1_000_000)
+}
+
+pub fn validate_message(message: &Message) -> ValidateCallbackResult {
+    if message.content.len() <= MAX_MESSAGE_LENGTH {
+        ValidateCallbackResult::Valid
+    } else {
+        ValidateCallbackResult::Invalid("Message too long".to_string())
+    }
+}
+
+/// Checks a channel info against the header that committed it
+pub fn validate_channel_info(
+    info: &ChannelInfo,
+    author: &AgentPubKey,
+    header_timestamp: Timestamp,
+) -> ValidateCallbackResult {
+    if info.created_by != *author {
+        return ValidateCallbackResult::Invalid(
+            "Channel info must be created by its author".to_string(),
+        );
+    }
+    if !within_tolerance(
+        info.created_at,
+        header_timestamp,
+        CHANNEL_INFO_TIMESTAMP_TOLERANCE_SECONDS,
+    ) {
+        return ValidateCallbackResult::Invalid(
+            "Channel info created_at is too far from the header timestamp".to_string(),
+        );
+    }
+    let name_length = info.name.trim().chars().count();
+    if name_length == 0 {
+        return ValidateCallbackResult::Invalid("Channel name is empty".to_string());
+    }
+    if info.name.chars().count() > MAX_CHANNEL_NAME_LENGTH {
+        return ValidateCallbackResult::Invalid("Channel name too long".to_string());
+    }
+    if info.category.is_empty() {
+        return ValidateCallbackResult::Invalid("Channel category is empty".to_string());
+    }
+    if info.category.len() > MAX_CATEGORY_LENGTH {
+        return ValidateCallbackResult::Invalid("Channel category too long".to_string());
+    }
+    if Uuid::parse_str(&info.uuid).is_err() {
+        return ValidateCallbackResult::Invalid("Channel uuid is not a valid uuid".to_string());
+    }
+    if info
+        .slow_mode_seconds
+        .map_or(false, |seconds| seconds > MAX_SLOW_MODE_SECONDS)
+    {
+        return ValidateCallbackResult::Invalid("Channel slow mode too long".to_string());
+    }
+    ValidateCallbackResult::Valid
+}
+
+/// Checks a category info against the header that committed it
+pub fn validate_category_info(
+    info: &CategoryInfo,
+    author: &AgentPubKey,
+    header_timestamp: Timestamp,
+) -> ValidateCallbackResult {
+    if info.created_by != *author {
+        return ValidateCallbackResult::Invalid(
+            "Category info must be created by its author".to_string(),
+        );
+    }
+    if !within_tolerance(
+        info.created_at,
+        header_timestamp,
+        CATEGORY_INFO_TIMESTAMP_TOLERANCE_SECONDS,
+    ) {
+        return ValidateCallbackResult::Invalid(
+            "Category info created_at is too far from the header timestamp".to_string(),
+        );
+    }
+    if info.name.trim().is_empty() {
+        return ValidateCallbackResult::Invalid("Category name is empty".to_string());
+    }
+    if info.name.chars().count() > MAX_CATEGORY_NAME_LENGTH {
+        return ValidateCallbackResult::Invalid("Category name too long".to_string());
+    }
+    if info.category.is_empty() {
+        return ValidateCallbackResult::Invalid("Category is empty".to_string());
+    }
+    if info.category.len() > MAX_CATEGORY_LENGTH {
+        return ValidateCallbackResult::Invalid("Category too long".to_string());
+    }
+    ValidateCallbackResult::Valid
+}
+
+/// A moderation action must be taken by its author, who must hold the role it needs on its channel,
+/// and point at a subject of the right type
+pub fn validate_moderation_action(
+    action: &ModerationAction,
+    author: &AgentPubKey,
+    header_timestamp: Timestamp,
+) -> ExternResult<ValidateCallbackResult> {
+    if action.moderator != *author {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Moderation action must be taken by its author".to_string(),
+        ));
+    }
+    if !within_tolerance(
+        action.created_at,
+        header_timestamp,
+        MODERATION_ACTION_TIMESTAMP_TOLERANCE_SECONDS,
+    ) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Moderation action created_at is too far from the header timestamp".to_string(),
+        ));
+    }
+    if action.reason.chars().count() > MAX_MODERATION_REASON_LENGTH {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Moderation reason too long".to_string(),
+        ));
+    }
+    let channel_path = match Path::try_from(action.channel.clone()) {
+        Ok(path) => path,
+        Err(_) => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Moderation action has an invalid channel uuid".to_string(),
+            ))
+        }
+    };
+    match &action.kind {
+        ModerationKind::HideMessage(message_hash) => {
+            let target = must_get_entry(message_hash.clone())?.into_content();
+            if Message::try_from(&target).is_err() {
+                return Ok(ValidateCallbackResult::Invalid(
+                    "Only messages can be hidden".to_string(),
+                ));
+            }
+        }
+        ModerationKind::Mute { until, .. } => {
+            if *until <= action.created_at {
+                return Ok(ValidateCallbackResult::Invalid(
+                    "Mute must end after it starts".to_string(),
+                ));
+            }
+        }
+        ModerationKind::ResolveReport(report_hash) | ModerationKind::DismissReport(report_hash) => {
+            let target = must_get_entry(report_hash.clone())?.into_content();
+            match Report::try_from(&target) {
+                Ok(report) if report.channel == action.channel => (),
+                _ => {
+                    return Ok(ValidateCallbackResult::Invalid(
+                        "Only reports on the same channel can be closed".to_string(),
                     ))
                 }
             }
-            _ => Ok(ValidateCallbackResult::Valid),
+        }
+        ModerationKind::Ban(_) | ModerationKind::Unban(_) => (),
+    }
+    validate_role_authority(
+        &action.authority,
+        author,
+        &channel_path.path_entry_hash()?,
+        action.kind.needed_role(),
+    )
+}
+
+/// A report must be made by its author about a message on a valid channel
+pub fn validate_report(
+    report: &Report,
+    author: &AgentPubKey,
+    header_timestamp: Timestamp,
+) -> ExternResult<ValidateCallbackResult> {
+    if report.reporter != *author {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Report must be made by its author".to_string(),
+        ));
+    }
+    if !within_tolerance(
+        report.created_at,
+        header_timestamp,
+        REPORT_TIMESTAMP_TOLERANCE_SECONDS,
+    ) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Report created_at is too far from the header timestamp".to_string(),
+        ));
+    }
+    if report.reason.chars().count() > MAX_REPORT_REASON_LENGTH {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Report reason too long".to_string(),
+        ));
+    }
+    if Path::try_from(report.channel.clone()).is_err() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Report has an invalid channel uuid".to_string(),
+        ));
+    }
+    let target = must_get_entry(report.message.clone())?.into_content();
+    if Message::try_from(&target).is_err() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only messages can be reported".to_string(),
+        ));
+    }
+    validate_previous_reports(report, author)
+}
+
+/// Walks the reports cited back from `report` and checks none of them
+/// were made by someone else or reported the same message.
+fn validate_previous_reports(
+    report: &Report,
+    author: &AgentPubKey,
+) -> ExternResult<ValidateCallbackResult> {
+    let mut previous = report.previous.clone();
+    while let Some(header_hash) = previous {
+        let element = must_get_valid_element(header_hash)?;
+        let earlier = match (element.header(), element.entry().to_app_option::<Report>()) {
+            (Header::Create(create), Ok(Some(earlier))) if create.author == *author => earlier,
+            _ => {
+                return Ok(ValidateCallbackResult::Invalid(
+                    "Report must cite one of its reporter's reports as the previous one"
+                        .to_string(),
+                ))
+            }
+        };
+        if earlier.message == report.message {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Each agent can only report a message once".to_string(),
+            ));
+        }
+        previous = earlier.previous;
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+pub fn __validate_create_link(create_link: &CreateLink) -> ExternResult<ValidateCallbackResult> {
+    if create_link.tag == ChannelInfoTag::tag() {
+        return validate_channel_info_link(create_link);
+    }
+    if let Some(category_hash) = CategoryTag::category_hash(&create_link.tag) {
+        return validate_category_link(create_link, category_hash);
+    }
+    if let Some((channel_hash, summary)) = ChannelIndexTag::parse(&create_link.tag) {
+        return validate_channel_index_link(create_link, channel_hash, summary);
+    }
+    if let Some(tag) = RoleTag::parse(&create_link.tag) {
+        return validate_role_link(create_link, tag);
+    }
+    if let Some(kind) = ModerationTag::parse(&create_link.tag) {
+        return validate_moderation_link(create_link, kind);
+    }
+    if create_link.tag == ReportTag::tag() {
+        return validate_report_link(create_link);
+    }
+    if let Some(pkg) = MembershipTag::pkg(&create_link.tag) {
+        return validate_membership_link(create_link, pkg);
+    }
+    match LastSeenKey::try_from(create_link.tag.clone()) {
+        Ok(key) => validate_message_link(create_link, key),
+        // Path and chatter links carry other tags, but a message must always
+        // be linked with a last seen key.
+        Err(_) => {
+            let target = must_get_entry(create_link.target_address.clone())?.into_content();
+            if Message::try_from(&target).is_ok() {
+                Ok(ValidateCallbackResult::Invalid(
+                    "Message links must carry a last seen tag".to_string(),
+                ))
+            } else {
+                Ok(ValidateCallbackResult::Valid)
+            }
+        }
+    }
+}
+
+/// A message link must point at a message, hang off
+/// the hour bucket of the channel and time in its tag, and keep to the rate limits.
+/// If it cites a channel info link that must be on the same channel.
+fn validate_message_link(
+    create_link: &CreateLink,
+    key: LastSeenKey,
+) -> ExternResult<ValidateCallbackResult> {
+    let target = must_get_entry(create_link.target_address.clone())?.into_content();
+    if Message::try_from(&target).is_err() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Message links must point to a message".to_string(),
+        ));
+    }
+    let (timestamp, channel) = match (key.timestamp, key.channel) {
+        (Some(timestamp), Some(channel)) => (timestamp, channel),
+        _ => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Message link tag is missing its timestamp or channel".to_string(),
+            ))
+        }
+    };
+    let channel_path = match Path::try_from(channel.clone()) {
+        Ok(path) => path,
+        Err(_) => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Message link tag has an invalid channel uuid".to_string(),
+            ))
+        }
+    };
+    let bucket = timestamp_into_path(channel_path.clone(), timestamp)?;
+    if bucket.path_entry_hash()? != create_link.base_address {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Message link timestamp does not match its hour bucket".to_string(),
+        ));
+    }
+    let info = match &key.info {
+        Some(info_link) => match cited_channel_info(info_link, &channel_path)? {
+            Some(info) => Some(info),
+            None => {
+                return Ok(ValidateCallbackResult::Invalid(
+                    "Message link tag must cite an info link of its own channel".to_string(),
+                ))
+            }
         },
-        _ => Ok(ValidateCallbackResult::Valid),
+        None => None,
+    };
+    // The link's header time is checked rather than the tag's, which batched imports set freely
+    if let Some(error) = check_rate_limit(
+        &RateLimitConfig::load()?,
+        &channel,
+        info.as_ref(),
+        create_link.timestamp,
+        create_link.prev_header.clone(),
+    )? {
+        return Ok(ValidateCallbackResult::Invalid(error.to_string()));
+    }
+    validate_not_read_only(&create_link.prev_header)
+}
+
+/// The channel info behind a valid channel info link on the channel with this path,
+/// or `None` if the header is anything else
+fn cited_channel_info(
+    info_link: &HeaderHash,
+    channel_path: &Path,
+) -> ExternResult<Option<ChannelInfo>> {
+    let element = must_get_valid_element(info_link.clone())?;
+    let target = match element.header() {
+        Header::CreateLink(create_link)
+            if create_link.tag == ChannelInfoTag::tag()
+                && create_link.base_address == channel_path.path_entry_hash()? =>
+        {
+            create_link.target_address.clone()
+        }
+        _ => return Ok(None),
+    };
+    Ok(ChannelInfo::try_from(&must_get_entry(target)?.into_content()).ok())
+}
+
+/// A channel info link must point at a channel info of the channel it hangs off,
+/// be created by that info's author, and only replace an info by the same author
+fn validate_channel_info_link(create_link: &CreateLink) -> ExternResult<ValidateCallbackResult> {
+    let target = must_get_entry(create_link.target_address.clone())?.into_content();
+    let info = match ChannelInfo::try_from(&target) {
+        Ok(info) => info,
+        Err(_) => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Channel info links must point to a channel info".to_string(),
+            ))
+        }
+    };
+    let channel = Channel {
+        category: info.category.clone(),
+        uuid: info.uuid.clone(),
+    };
+    let channel_path = match Path::try_from(channel) {
+        Ok(path) => path,
+        Err(_) => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Channel info has an invalid uuid".to_string(),
+            ))
+        }
+    };
+    if channel_path.path_entry_hash()? != create_link.base_address {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Channel info must be linked from its own channel".to_string(),
+        ));
+    }
+    if info.created_by != create_link.author {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Channel info can only be linked by its creator".to_string(),
+        ));
+    }
+    let result = validate_previous_info(&info, &create_link.base_address)?;
+    if result != ValidateCallbackResult::Valid {
+        return Ok(result);
     }
+    validate_not_read_only(&create_link.prev_header)
+}
+
+/// A channel's first info must be by the owner its uuid names,
+/// and an info that replaces another must cite the other's info link,
+/// which must be on the same channel and by the same author.
+/// That link was checked the same way, so every info back to the channel's first is by its owner.
+fn validate_previous_info(
+    info: &ChannelInfo,
+    channel_hash: &EntryHash,
+) -> ExternResult<ValidateCallbackResult> {
+    let previous = match &info.previous {
+        Some(previous) => previous,
+        None if is_owner_uuid(&info.uuid, &info.created_by) => {
+            return Ok(ValidateCallbackResult::Valid)
+        }
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Only the owner named by the channel uuid can create the channel".to_string(),
+            ))
+        }
+    };
+    match must_get_valid_element(previous.clone())?.header() {
+        Header::CreateLink(previous_link)
+            if previous_link.tag == ChannelInfoTag::tag()
+                && previous_link.base_address == *channel_hash
+                && previous_link.author == info.created_by =>
+        {
+            Ok(ValidateCallbackResult::Valid)
+        }
+        _ => Ok(ValidateCallbackResult::Invalid(
+            ChatError::NotChannelAuthor.to_string(),
+        )),
+    }
+}
+
+/// A channel index link must point at a channel info, hang off that info's category,
+/// name the info's channel in its tag with a matching summary, and be created by that info's author,
+/// who must also be the author of any info it replaces
+fn validate_channel_index_link(
+    create_link: &CreateLink,
+    channel_hash: EntryHash,
+    summary: ChannelSummary,
+) -> ExternResult<ValidateCallbackResult> {
+    let target = must_get_entry(create_link.target_address.clone())?.into_content();
+    let info = match ChannelInfo::try_from(&target) {
+        Ok(info) => info,
+        Err(_) => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Channel index links must point to a channel info".to_string(),
+            ))
+        }
+    };
+    if category_path(&info.category).path_entry_hash()? != create_link.base_address {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Channel info must be indexed on its own category".to_string(),
+        ));
+    }
+    let channel = Channel {
+        category: info.category.clone(),
+        uuid: info.uuid.clone(),
+    };
+    let channel_path = match Path::try_from(channel) {
+        Ok(path) => path,
+        Err(_) => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Channel info has an invalid uuid".to_string(),
+            ))
+        }
+    };
+    if channel_path.path_entry_hash()? != channel_hash {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Channel index tag does not match the channel info".to_string(),
+        ));
+    }
+    if summary != ChannelSummary::from(&info) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Channel index summary does not match the channel info".to_string(),
+        ));
+    }
+    if info.created_by != create_link.author {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Channel info can only be indexed by its creator".to_string(),
+        ));
+    }
+    let result = validate_previous_info(&info, &channel_hash)?;
+    if result != ValidateCallbackResult::Valid {
+        return Ok(result);
+    }
+    validate_not_read_only(&create_link.prev_header)
+}
+
+/// A category link must point at a category info, hang off the categories root,
+/// name the info's category in its tag, and be created by that info's author,
+/// who must also be the author of any info it replaces
+fn validate_category_link(
+    create_link: &CreateLink,
+    category_hash: EntryHash,
+) -> ExternResult<ValidateCallbackResult> {
+    let target = must_get_entry(create_link.target_address.clone())?.into_content();
+    let info = match CategoryInfo::try_from(&target) {
+        Ok(info) => info,
+        Err(_) => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Category links must point to a category info".to_string(),
+            ))
+        }
+    };
+    if categories_path().path_entry_hash()? != create_link.base_address {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Category info must be linked from the categories root".to_string(),
+        ));
+    }
+    if category_path(&info.category).path_entry_hash()? != category_hash {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Category tag does not match the category info".to_string(),
+        ));
+    }
+    if info.created_by != create_link.author {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Category info can only be linked by its creator".to_string(),
+        ));
+    }
+    let result = validate_previous_category(&info, &create_link.tag)?;
+    if result != ValidateCallbackResult::Valid {
+        return Ok(result);
+    }
+    validate_not_read_only(&create_link.prev_header)
+}
+
+/// A category info that replaces another must cite the other's category link,
+/// which must be for the same category and by the same author
+fn validate_previous_category(
+    info: &CategoryInfo,
+    tag: &LinkTag,
+) -> ExternResult<ValidateCallbackResult> {
+    let previous = match &info.previous {
+        Some(previous) => previous,
+        None => return Ok(ValidateCallbackResult::Valid),
+    };
+    match must_get_valid_element(previous.clone())?.header() {
+        Header::CreateLink(previous_link)
+            if previous_link.tag == *tag
+                && previous_link.base_address == categories_path().path_entry_hash()?
+                && previous_link.author == info.created_by =>
+        {
+            Ok(ValidateCallbackResult::Valid)
+        }
+        _ => Ok(ValidateCallbackResult::Invalid(
+            ChatError::NotCategoryAuthor.to_string(),
+        )),
+    }
+}
+
+/// A moderation link must point at a moderation action of the kind in its tag,
+/// hang off the action's channel or subject, and be created by the action's moderator
+fn validate_moderation_link(
+    create_link: &CreateLink,
+    kind: ModerationKind,
+) -> ExternResult<ValidateCallbackResult> {
+    let target = must_get_entry(create_link.target_address.clone())?.into_content();
+    let action = match ModerationAction::try_from(&target) {
+        Ok(action) => action,
+        Err(_) => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Moderation links must point to a moderation action".to_string(),
+            ))
+        }
+    };
+    if action.kind != kind {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Moderation tag does not match the action".to_string(),
+        ));
+    }
+    let channel_hash = match Path::try_from(action.channel.clone()) {
+        Ok(path) => path.path_entry_hash()?,
+        Err(_) => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Moderation action has an invalid channel uuid".to_string(),
+            ))
+        }
+    };
+    if create_link.base_address != channel_hash && create_link.base_address != kind.subject() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Moderation actions must be linked from their channel or subject".to_string(),
+        ));
+    }
+    if action.moderator != create_link.author {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Moderation actions can only be linked by their moderator".to_string(),
+        ));
+    }
+    validate_not_read_only(&create_link.prev_header)
+}
+
+/// A report link must point at a report, hang off the report queue
+/// of the report's channel, and be created by the reporter
+fn validate_report_link(create_link: &CreateLink) -> ExternResult<ValidateCallbackResult> {
+    let target = must_get_entry(create_link.target_address.clone())?.into_content();
+    let report = match Report::try_from(&target) {
+        Ok(report) => report,
+        Err(_) => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Report links must point to a report".to_string(),
+            ))
+        }
+    };
+    let queue = match reports_path(&report.channel) {
+        Ok(path) => path,
+        Err(_) => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Report has an invalid channel uuid".to_string(),
+            ))
+        }
+    };
+    if queue.path_entry_hash()? != create_link.base_address {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Reports must be linked from their channel's report queue".to_string(),
+        ));
+    }
+    if report.reporter != create_link.author {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Reports can only be linked by their reporter".to_string(),
+        ));
+    }
+    validate_not_read_only(&create_link.prev_header)
+}
+
+/// A role link must point at an agent, and cite a header showing its author
+/// held the role needed to grant or revoke that role on the same channel.
+fn validate_role_link(
+    create_link: &CreateLink,
+    tag: RoleTag,
+) -> ExternResult<ValidateCallbackResult> {
+    let needed = match tag.role.granted_by() {
+        Some(needed) => needed,
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Channel ownership can't be granted or revoked".to_string(),
+            ))
+        }
+    };
+    let result = validate_role_authority(
+        &tag.authority,
+        &create_link.author,
+        &create_link.base_address,
+        needed,
+    )?;
+    if result != ValidateCallbackResult::Valid {
+        return Ok(result);
+    }
+    validate_not_read_only(&create_link.prev_header)
+}
+
+/// Checks that `authority` shows `author` held `needed` or a higher role
+/// on the channel at `channel_hash`.
+///
+/// The authority is either one of the author's channel info links, which makes them the owner
+/// since only the owner the uuid names can link infos, or a role link granting them their role.
+/// Both were validated when they were created, so this only has to look one step back.
+pub(crate) fn validate_role_authority(
+    authority: &HeaderHash,
+    author: &AgentPubKey,
+    channel_hash: &EntryHash,
+    needed: ChannelRole,
+) -> ExternResult<ValidateCallbackResult> {
+    let element = must_get_valid_element(authority.clone())?;
+    let authority_link = match element.header() {
+        Header::CreateLink(create_link) if create_link.base_address == *channel_hash => create_link,
+        _ => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Role authority must be a link on the same channel".to_string(),
+            ))
+        }
+    };
+    let held = if authority_link.tag == ChannelInfoTag::tag() && authority_link.author == *author {
+        ChannelRole::Owner
+    } else {
+        match RoleTag::parse(&authority_link.tag) {
+            Some(grant)
+                if grant.granted
+                    && authority_link.target_address == EntryHash::from(author.clone()) =>
+            {
+                grant.role
+            }
+            _ => {
+                return Ok(ValidateCallbackResult::Invalid(
+                    "Role authority doesn't grant the author a role".to_string(),
+                ))
+            }
+        }
+    };
+    if held < needed {
+        return Ok(ValidateCallbackResult::Invalid(
+            ChatError::NotAuthorized.to_string(),
+        ));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// Rejects chat data from agents that joined with a read-only membrane proof.
+///
+/// Walks back the author's chain from `prev_header` to their last chat link,
+/// which was put through this same check when it was validated, so it's enough that it is valid.
+/// A membership link or the `AgentValidationPkg` itself ends the walk by citing or being the proof.
+/// One of those must be within `READ_ONLY_CHECK_WINDOW_HEADERS` headers,
+/// so the walk costs the same however long the chain is.
+fn validate_not_read_only(prev_header: &HeaderHash) -> ExternResult<ValidateCallbackResult> {
+    let mut header_hash = prev_header.clone();
+    for _ in 0..READ_ONLY_CHECK_WINDOW_HEADERS {
+        let signed_header = must_get_header(header_hash)?;
+        match signed_header.header() {
+            Header::AgentValidationPkg(pkg) => return Ok(check_membrane_proof(pkg)),
+            Header::CreateLink(create_link) if is_chat_link(&create_link.tag) => {
+                must_get_valid_element(signed_header.header_address().clone())?;
+                return Ok(ValidateCallbackResult::Valid);
+            }
+            Header::CreateLink(create_link) => {
+                if let Some(pkg) = MembershipTag::pkg(&create_link.tag) {
+                    return match must_get_header(pkg)?.header() {
+                        Header::AgentValidationPkg(pkg) if pkg.author == create_link.author => {
+                            Ok(check_membrane_proof(pkg))
+                        }
+                        _ => Ok(ValidateCallbackResult::Invalid(
+                            "Membership link must cite its author's validation pkg".to_string(),
+                        )),
+                    };
+                }
+                header_hash = create_link.prev_header.clone();
+            }
+            header => match header.prev_header() {
+                Some(prev) => header_hash = prev.clone(),
+                None => return Ok(ValidateCallbackResult::Valid),
+            },
+        }
+    }
+    Ok(ValidateCallbackResult::Invalid(
+        "No chat or membership link close enough to check the author isn't read-only".to_string(),
+    ))
+}
+
+fn check_membrane_proof(pkg: &AgentValidationPkg) -> ValidateCallbackResult {
+    let read_only = pkg
+        .membrane_proof
+        .as_ref()
+        .map(hc_joining_code::is_read_only_proof)
+        .unwrap_or(false);
+    if read_only {
+        ValidateCallbackResult::Invalid(ChatError::ReadOnly.to_string())
+    } else {
+        ValidateCallbackResult::Valid
+    }
+}
+
+/// A membership link must go from its author to themselves and cite their own validation pkg
+fn validate_membership_link(
+    create_link: &CreateLink,
+    pkg: HeaderHash,
+) -> ExternResult<ValidateCallbackResult> {
+    let author = EntryHash::from(create_link.author.clone());
+    if create_link.base_address != author || create_link.target_address != author {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Membership links must go from their author to themselves".to_string(),
+        ));
+    }
+    match must_get_header(pkg)?.header() {
+        Header::AgentValidationPkg(pkg) if pkg.author == create_link.author => {
+            Ok(ValidateCallbackResult::Valid)
+        }
+        _ => Ok(ValidateCallbackResult::Invalid(
+            "Membership link must cite its author's validation pkg".to_string(),
+        )),
+    }
+}
+
+/// Whether a link carries one of the tags whose validation includes the read-only check
+pub(crate) fn is_chat_link(tag: &LinkTag) -> bool {
+    *tag == ChannelInfoTag::tag()
+        || *tag == ReportTag::tag()
+        || CategoryTag::category_hash(tag).is_some()
+        || ChannelIndexTag::parse(tag).is_some()
+        || RoleTag::parse(tag).is_some()
+        || ModerationTag::parse(tag).is_some()
+        || matches!(
+            LastSeenKey::try_from(tag.clone()),
+            Ok(LastSeenKey {
+                timestamp: Some(_),
+                channel: Some(_),
+                ..
+            })
+        )
 }
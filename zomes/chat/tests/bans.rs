@@ -0,0 +1,159 @@
+use chat::*;
+use hc_joining_code::Props;
+use holochain::conductor::api::error::ConductorApiResult;
+use holochain::sweettest::*;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn channel_owners_ban_and_mute_agents() {
+    // Use prebuilt DNA bundle.
+    // You must build the DNA bundle as a separate step before running the test.
+    let dna_path = std::env::current_dir()
+        .unwrap()
+        .join("../../elemental-chat.dna");
+
+    let dna = SweetDnaFile::from_bundle_with_overrides(
+        &dna_path,
+        None,
+        Some(Props {
+            skip_proof: true,
+            holo_agent_override: None,
+            development_stage: None,
+            t_and_c: None,
+            t_and_c_agreement: None,
+        }),
+    )
+    .await
+    .unwrap();
+
+    let mut conductor = SweetConductor::from_standard_config().await;
+    let agents = SweetAgents::get(conductor.keystore(), 3).await;
+    let apps = conductor
+        .setup_app_for_agents("elemental-chat", &agents, &[dna])
+        .await
+        .unwrap();
+    let ((alice_cell,), (bobbo_cell,), (carol_cell,)) = apps.into_tuples();
+    let alice_chat = &alice_cell.zome("chat");
+    let bobbo_chat = &bobbo_cell.zome("chat");
+    let carol_chat = &carol_cell.zome("chat");
+    let bobbo = bobbo_cell.agent_pubkey().clone();
+    let carol = carol_cell.agent_pubkey().clone();
+
+    let channel = Channel {
+        category: "General".into(),
+        uuid: Channel::owned_uuid(alice_cell.agent_pubkey(), uuid::Uuid::new_v4().as_bytes()),
+    };
+    let _: ChannelData = conductor
+        .call(
+            alice_chat,
+            "create_channel",
+            ChannelInput {
+                name: "Strict".into(),
+                entry: channel.clone(),
+            },
+        )
+        .await;
+    for chat in [bobbo_chat, carol_chat] {
+        let _: () = conductor.call(chat, "refresh_chatter", ()).await;
+    }
+
+    let message = |content: &str| MessageInput {
+        last_seen: LastSeen::First,
+        channel: channel.clone(),
+        entry: Message {
+            uuid: uuid::Uuid::new_v4().to_string(),
+            content: content.into(),
+        },
+        signal: None,
+    };
+    let before: CreatedMessage = conductor
+        .call(bobbo_chat, "create_message", message("Before the ban"))
+        .await;
+
+    // TODO: add consistency awaiting to sweettest
+    tokio::time::sleep(tokio::time::Duration::from_millis(4000)).await;
+
+    let ban = |agent: &AgentPubKey| BanInput {
+        channel: channel.clone(),
+        agent: agent.clone(),
+        reason: "Spam".into(),
+    };
+    // Only the owner can ban
+    let not_owner: ConductorApiResult<ModerationAction> = conductor
+        .call_fallible(carol_chat, "ban_agent", ban(&bobbo))
+        .await;
+    assert!(not_owner.is_err());
+
+    let _: ModerationAction = conductor.call(alice_chat, "ban_agent", ban(&bobbo)).await;
+    let _: ModerationAction = conductor
+        .call(
+            alice_chat,
+            "mute_agent",
+            MuteInput {
+                channel: channel.clone(),
+                agent: carol.clone(),
+                reason: "Cool off".into(),
+                seconds: 60 * 60,
+            },
+        )
+        .await;
+
+    tokio::time::sleep(tokio::time::Duration::from_millis(4000)).await;
+
+    let banned: ConductorApiResult<CreatedMessage> = conductor
+        .call_fallible(bobbo_chat, "create_message", message("During the ban"))
+        .await;
+    assert!(banned.is_err());
+    let muted: ConductorApiResult<CreatedMessage> = conductor
+        .call_fallible(carol_chat, "create_message", message("While muted"))
+        .await;
+    assert!(muted.is_err());
+
+    let chatters: ActiveChatters = conductor
+        .call(alice_chat, "get_channel_chatters", channel.clone())
+        .await;
+    assert!(chatters.chatters.is_empty());
+
+    let list_messages = ListMessagesInput {
+        channel: channel.clone(),
+        earliest_seen: None,
+        target_message_count: 10,
+        include_hidden: None,
+    };
+    // Messages from before the ban stay listed
+    let during: ListMessages = conductor
+        .call(alice_chat, "list_messages", list_messages.clone())
+        .await;
+    assert_eq!(during.messages, vec![before.message.clone()]);
+
+    let _: ModerationAction = conductor.call(alice_chat, "unban_agent", ban(&bobbo)).await;
+
+    tokio::time::sleep(tokio::time::Duration::from_millis(4000)).await;
+
+    let after: CreatedMessage = conductor
+        .call(bobbo_chat, "create_message", message("After the ban"))
+        .await;
+    let chatters: ActiveChatters = conductor
+        .call(alice_chat, "get_channel_chatters", channel.clone())
+        .await;
+    assert_eq!(chatters.chatters, vec![bobbo]);
+
+    tokio::time::sleep(tokio::time::Duration::from_millis(4000)).await;
+
+    let list: ListMessages = conductor
+        .call(alice_chat, "list_messages", list_messages)
+        .await;
+    assert_eq!(list.messages, vec![before.message, after.message]);
+
+    let log: ModerationLog = conductor
+        .call(
+            bobbo_chat,
+            "get_moderation_log",
+            ModerationLogInput {
+                channel,
+                moderator: None,
+                since: None,
+            },
+        )
+        .await;
+    assert_eq!(log.actions.len(), 3);
+}
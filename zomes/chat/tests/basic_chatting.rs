@@ -54,7 +54,10 @@ async fn chat_away() {
                 name: "Test Ch".into(),
                 entry: Channel {
                     category: "General".into(),
-                    uuid: uuid::Uuid::new_v4().to_string(),
+                    uuid: Channel::owned_uuid(
+                        alice_cell.agent_pubkey(),
+                        uuid::Uuid::new_v4().as_bytes(),
+                    ),
                 },
             },
         )
@@ -67,6 +70,7 @@ async fn chat_away() {
             uuid: "long msg".into(),
             content: std::iter::repeat('x').take(1025).collect(),
         },
+        signal: None,
     };
 
     let error: ConductorApiResult<MessageData> = conductor
@@ -101,6 +105,7 @@ async fn chat_away() {
         channel: channel.entry.clone(),
         earliest_seen: None,
         target_message_count: 1,
+        include_hidden: None,
     };
 
     let alice_msgs: ListMessages = conductor
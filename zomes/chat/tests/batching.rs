@@ -101,7 +101,10 @@ impl SharedTestState {
                     name: self.next_channel_name(),
                     entry: Channel {
                         category: "General".into(),
-                        uuid: uuid::Uuid::new_v4().to_string(),
+                        uuid: Channel::owned_uuid(
+                            self.alice_chat.cell_id().agent_pubkey(),
+                            uuid::Uuid::new_v4().as_bytes(),
+                        ),
                     },
                 },
             )
@@ -129,6 +132,7 @@ impl SharedTestState {
                     channel: channel.entry,
                     earliest_seen: Some(test_input.earliest_seen),
                     target_message_count: test_input.target_message_count,
+                    include_hidden: None,
                 },
             ),
         )
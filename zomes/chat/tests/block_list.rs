@@ -0,0 +1,109 @@
+use chat::*;
+use hc_joining_code::Props;
+use holochain::sweettest::*;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn blocked_agents_are_filtered_out() {
+    // Use prebuilt DNA bundle.
+    // You must build the DNA bundle as a separate step before running the test.
+    let dna_path = std::env::current_dir()
+        .unwrap()
+        .join("../../elemental-chat.dna");
+
+    let dna = SweetDnaFile::from_bundle_with_overrides(
+        &dna_path,
+        None,
+        Some(Props {
+            skip_proof: true,
+            holo_agent_override: None,
+            development_stage: None,
+            t_and_c: None,
+            t_and_c_agreement: None,
+        }),
+    )
+    .await
+    .unwrap();
+
+    let mut conductor = SweetConductor::from_standard_config().await;
+    let agents = SweetAgents::get(conductor.keystore(), 3).await;
+    let apps = conductor
+        .setup_app_for_agents("elemental-chat", &agents, &[dna])
+        .await
+        .unwrap();
+    let ((alice_cell,), (bobbo_cell,), (carol_cell,)) = apps.into_tuples();
+    let alice_chat = &alice_cell.zome("chat");
+    let bobbo_chat = &bobbo_cell.zome("chat");
+    let carol_chat = &carol_cell.zome("chat");
+    let bobbo = bobbo_cell.agent_pubkey().clone();
+    let carol = carol_cell.agent_pubkey().clone();
+
+    let channel = Channel {
+        category: "General".into(),
+        uuid: Channel::owned_uuid(alice_cell.agent_pubkey(), uuid::Uuid::new_v4().as_bytes()),
+    };
+    let _: ChannelData = conductor
+        .call(
+            alice_chat,
+            "create_channel",
+            ChannelInput {
+                name: "Open".into(),
+                entry: channel.clone(),
+            },
+        )
+        .await;
+    let message = |content: &str| MessageInput {
+        last_seen: LastSeen::First,
+        channel: channel.clone(),
+        entry: Message {
+            uuid: uuid::Uuid::new_v4().to_string(),
+            content: content.into(),
+        },
+        signal: None,
+    };
+    let harassment: CreatedMessage = conductor
+        .call(bobbo_chat, "create_message", message("Harassment"))
+        .await;
+    let friendly: CreatedMessage = conductor
+        .call(carol_chat, "create_message", message("Hi"))
+        .await;
+    for chat in [bobbo_chat, carol_chat] {
+        let _: () = conductor.call(chat, "refresh_chatter", ()).await;
+    }
+
+    let list: BlockList = conductor
+        .call(alice_chat, "block_agent", bobbo.clone())
+        .await;
+    assert_eq!(list.agents, vec![bobbo.clone()]);
+
+    // TODO: add consistency awaiting to sweettest
+    tokio::time::sleep(tokio::time::Duration::from_millis(4000)).await;
+
+    let list_input = ListMessagesInput {
+        channel: channel.clone(),
+        earliest_seen: None,
+        target_message_count: 10,
+        include_hidden: None,
+    };
+    let alice_view: ListMessages = conductor
+        .call(alice_chat, "list_messages", list_input.clone())
+        .await;
+    assert_eq!(alice_view.messages, vec![friendly.message.clone()]);
+    let chatters: ActiveChatters = conductor.call(alice_chat, "get_active_chatters", ()).await;
+    assert_eq!(chatters.chatters, vec![carol.clone()]);
+
+    // Blocking only changes what the blocker sees
+    let carol_view: ListMessages = conductor
+        .call(carol_chat, "list_messages", list_input.clone())
+        .await;
+    assert_eq!(carol_view.messages.len(), 2);
+
+    let list: BlockList = conductor.call(alice_chat, "unblock_agent", bobbo).await;
+    assert!(list.agents.is_empty());
+    let alice_view: ListMessages = conductor
+        .call(alice_chat, "list_messages", list_input)
+        .await;
+    assert_eq!(
+        alice_view.messages,
+        vec![harassment.message, friendly.message]
+    );
+}
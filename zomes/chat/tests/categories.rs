@@ -0,0 +1,160 @@
+use chat::*;
+use hc_joining_code::Props;
+use holochain::conductor::api::error::ConductorApiResult;
+use holochain::sweettest::*;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn categories_can_be_created_renamed_and_moved() {
+    // Use prebuilt DNA bundle.
+    // You must build the DNA bundle as a separate step before running the test.
+    let dna_path = std::env::current_dir()
+        .unwrap()
+        .join("../../elemental-chat.dna");
+
+    let dna = SweetDnaFile::from_bundle_with_overrides(
+        &dna_path,
+        None,
+        Some(Props {
+            skip_proof: true,
+            holo_agent_override: None,
+            development_stage: None,
+            t_and_c: None,
+            t_and_c_agreement: None,
+        }),
+    )
+    .await
+    .unwrap();
+
+    let mut conductor = SweetConductor::from_standard_config().await;
+    let agents = SweetAgents::get(conductor.keystore(), 2).await;
+    let apps = conductor
+        .setup_app_for_agents("elemental-chat", &agents, &[dna])
+        .await
+        .unwrap();
+    let ((alice_cell,), (bobbo_cell,)) = apps.into_tuples();
+    let alice_chat = &alice_cell.zome("chat");
+    let bobbo_chat = &bobbo_cell.zome("chat");
+
+    let general: CategoryInfo = conductor
+        .call(
+            alice_chat,
+            "create_category",
+            CategoryInput {
+                category: "General".into(),
+                name: "General".into(),
+                position: None,
+            },
+        )
+        .await;
+    assert_eq!(general.position, 0);
+    let random: CategoryInfo = conductor
+        .call(
+            alice_chat,
+            "create_category",
+            CategoryInput {
+                category: "Random".into(),
+                name: "Random".into(),
+                position: None,
+            },
+        )
+        .await;
+    assert_eq!(random.position, 1);
+
+    let again: ConductorApiResult<CategoryInfo> = conductor
+        .call_fallible(
+            alice_chat,
+            "create_category",
+            CategoryInput {
+                category: "General".into(),
+                name: "Again".into(),
+                position: None,
+            },
+        )
+        .await;
+    assert!(again.is_err());
+
+    let renamed: CategoryInfo = conductor
+        .call(
+            alice_chat,
+            "rename_category",
+            RenameCategoryInput {
+                category: "General".into(),
+                name: "Main".into(),
+            },
+        )
+        .await;
+    assert_eq!(renamed.position, 0);
+    let moved: CategoryInfo = conductor
+        .call(
+            alice_chat,
+            "move_category",
+            MoveCategoryInput {
+                category: "General".into(),
+                position: 2,
+            },
+        )
+        .await;
+    assert_eq!(moved.name, "Main");
+
+    // TODO: add consistency awaiting to sweettest
+    tokio::time::sleep(tokio::time::Duration::from_millis(4000)).await;
+
+    // Only the author can change a category
+    let hijack: ConductorApiResult<CategoryInfo> = conductor
+        .call_fallible(
+            bobbo_chat,
+            "rename_category",
+            RenameCategoryInput {
+                category: "Random".into(),
+                name: "Mine".into(),
+            },
+        )
+        .await;
+    assert!(hijack.is_err());
+
+    let list: CategoryList = conductor.call(bobbo_chat, "list_categories", ()).await;
+    assert_eq!(list.categories, vec![random.clone(), moved.clone()]);
+
+    // Creating a channel registers its category
+    let create_channel = |owner: &AgentPubKey, category: &str, name: &str| ChannelInput {
+        name: name.into(),
+        entry: Channel {
+            category: category.into(),
+            uuid: Channel::owned_uuid(owner, uuid::Uuid::new_v4().as_bytes()),
+        },
+    };
+    let off_topic: ChannelData = conductor
+        .call(
+            bobbo_chat,
+            "create_channel",
+            create_channel(bobbo_cell.agent_pubkey(), "Off topic", "Memes"),
+        )
+        .await;
+    let main: ChannelData = conductor
+        .call(
+            alice_chat,
+            "create_channel",
+            create_channel(alice_cell.agent_pubkey(), "General", "Hello"),
+        )
+        .await;
+
+    // TODO: add consistency awaiting to sweettest
+    tokio::time::sleep(tokio::time::Duration::from_millis(4000)).await;
+
+    let list: CategoryList = conductor.call(alice_chat, "list_categories", ()).await;
+    assert_eq!(list.categories.len(), 3);
+    assert_eq!(list.categories[..2], [random, moved]);
+    let registered = &list.categories[2];
+    assert_eq!(registered.category, "Off topic");
+    assert_eq!(registered.name, "Off topic");
+    assert_eq!(registered.position, 3);
+    assert_eq!(registered.created_by, *bobbo_cell.agent_pubkey());
+
+    // Without a category every registered category is listed
+    let list: ChannelList = conductor
+        .call(alice_chat, "list_channels", ChannelListInput::default())
+        .await;
+    let mut names: Vec<String> = list.channels.into_iter().map(|c| c.info.name).collect();
+    names.sort();
+    assert_eq!(names, vec![main.info.name, off_topic.info.name]);
+}
@@ -0,0 +1,126 @@
+use chat::*;
+use hc_joining_code::Props;
+use holochain::conductor::api::error::ConductorApiResult;
+use holochain::sweettest::*;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn list_channels_uses_the_latest_indexed_info() {
+    // Use prebuilt DNA bundle.
+    // You must build the DNA bundle as a separate step before running the test.
+    let dna_path = std::env::current_dir()
+        .unwrap()
+        .join("../../elemental-chat.dna");
+
+    let dna = SweetDnaFile::from_bundle_with_overrides(
+        &dna_path,
+        None,
+        Some(Props {
+            skip_proof: true,
+            holo_agent_override: None,
+            development_stage: None,
+            t_and_c: None,
+            t_and_c_agreement: None,
+        }),
+    )
+    .await
+    .unwrap();
+
+    let mut conductor = SweetConductor::from_standard_config().await;
+    let agents = SweetAgents::get(conductor.keystore(), 2).await;
+    let apps = conductor
+        .setup_app_for_agents("elemental-chat", &agents, &[dna])
+        .await
+        .unwrap();
+    let ((alice_cell,), (bobbo_cell,)) = apps.into_tuples();
+    let alice_chat = &alice_cell.zome("chat");
+    let bobbo_chat = &bobbo_cell.zome("chat");
+
+    let create_channel = |name: &str| ChannelInput {
+        name: name.into(),
+        entry: Channel {
+            category: "General".into(),
+            uuid: Channel::owned_uuid(alice_cell.agent_pubkey(), uuid::Uuid::new_v4().as_bytes()),
+        },
+    };
+    let mut created = Vec::new();
+    for name in ["One", "Two", "Three"] {
+        let channel: ChannelData = conductor
+            .call(alice_chat, "create_channel", create_channel(name))
+            .await;
+        created.push(channel);
+    }
+    // A channel in another category isn't listed
+    let _: ChannelData = conductor
+        .call(
+            alice_chat,
+            "create_channel",
+            ChannelInput {
+                name: "Elsewhere".into(),
+                entry: Channel {
+                    category: "Other".into(),
+                    uuid: Channel::owned_uuid(
+                        alice_cell.agent_pubkey(),
+                        uuid::Uuid::new_v4().as_bytes(),
+                    ),
+                },
+            },
+        )
+        .await;
+
+    let renamed: ChannelData = conductor
+        .call(
+            alice_chat,
+            "rename_channel",
+            RenameChannelInput {
+                channel: created[0].entry.clone(),
+                name: "Renamed".into(),
+            },
+        )
+        .await;
+    let archived: ChannelData = conductor
+        .call(alice_chat, "archive_channel", created[1].entry.clone())
+        .await;
+    // Bobbo can't link an info of his own onto Alice's channel
+    let squatted: ConductorApiResult<ChannelData> = conductor
+        .call_fallible(
+            bobbo_chat,
+            "create_channel",
+            ChannelInput {
+                name: "Squatted".into(),
+                entry: created[2].entry.clone(),
+            },
+        )
+        .await;
+    assert!(squatted.is_err());
+
+    // TODO: add consistency awaiting to sweettest
+    tokio::time::sleep(tokio::time::Duration::from_millis(4000)).await;
+
+    let list = |include_archived| ChannelListInput {
+        category: Some("General".into()),
+        include_archived: Some(include_archived),
+        ..Default::default()
+    };
+    let mut all: ChannelList = conductor
+        .call(bobbo_chat, "list_channels", list(true))
+        .await;
+    // Listed channels can also carry their roles and last message, so only compare what was created
+    let mut listed: Vec<_> = all
+        .channels
+        .into_iter()
+        .map(|c| (c.entry, c.info))
+        .collect();
+    listed.sort_by_key(|(entry, _)| entry.clone());
+    let mut expected: Vec<_> = vec![renamed, archived, created[2].clone()]
+        .into_iter()
+        .map(|c| (c.entry, c.info))
+        .collect();
+    expected.sort_by_key(|(entry, _)| entry.clone());
+    assert_eq!(listed, expected);
+
+    let active: ChannelList = conductor
+        .call(bobbo_chat, "list_channels", list(false))
+        .await;
+    assert_eq!(active.channels.len(), 2);
+    assert!(active.channels.iter().all(|c| !c.info.archived));
+}
@@ -0,0 +1,200 @@
+use chat::*;
+use hc_joining_code::Props;
+use holochain::sweettest::*;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn list_channels_sorts_filters_and_pages() {
+    // Use prebuilt DNA bundle.
+    // You must build the DNA bundle as a separate step before running the test.
+    let dna_path = std::env::current_dir()
+        .unwrap()
+        .join("../../elemental-chat.dna");
+
+    let dna = SweetDnaFile::from_bundle_with_overrides(
+        &dna_path,
+        None,
+        Some(Props {
+            skip_proof: true,
+            holo_agent_override: None,
+            development_stage: None,
+            t_and_c: None,
+            t_and_c_agreement: None,
+        }),
+    )
+    .await
+    .unwrap();
+
+    let mut conductor = SweetConductor::from_standard_config().await;
+    let agents = SweetAgents::get(conductor.keystore(), 2).await;
+    let apps = conductor
+        .setup_app_for_agents("elemental-chat", &agents, &[dna])
+        .await
+        .unwrap();
+    let ((alice_cell,), (bobbo_cell,)) = apps.into_tuples();
+    let alice_chat = &alice_cell.zome("chat");
+    let bobbo_chat = &bobbo_cell.zome("chat");
+
+    let create_channel = |owner: &AgentPubKey, name: &str| ChannelInput {
+        name: name.into(),
+        entry: Channel {
+            category: "General".into(),
+            uuid: Channel::owned_uuid(owner, uuid::Uuid::new_v4().as_bytes()),
+        },
+    };
+    let mut gamma = None;
+    for name in ["Gamma", "alps", "Alpha", "delta"] {
+        let channel: ChannelData = conductor
+            .call(
+                alice_chat,
+                "create_channel",
+                create_channel(alice_cell.agent_pubkey(), name),
+            )
+            .await;
+        gamma.get_or_insert(channel);
+    }
+    let gamma = gamma.unwrap();
+    let _: ChannelData = conductor
+        .call(
+            bobbo_chat,
+            "create_channel",
+            create_channel(bobbo_cell.agent_pubkey(), "Beta"),
+        )
+        .await;
+
+    // TODO: add consistency awaiting to sweettest
+    tokio::time::sleep(tokio::time::Duration::from_millis(4000)).await;
+
+    let list = |input: ChannelListInput| {
+        let conductor = &conductor;
+        async move {
+            let list: ChannelList = conductor.call(alice_chat, "list_channels", input).await;
+            list
+        }
+    };
+    let names = |list: &ChannelList| -> Vec<String> {
+        list.channels.iter().map(|c| c.info.name.clone()).collect()
+    };
+    let general = || ChannelListInput {
+        category: Some("General".into()),
+        ..Default::default()
+    };
+
+    let by_name = list(ChannelListInput {
+        sort: Some(ChannelSort::Name),
+        ..general()
+    })
+    .await;
+    assert_eq!(
+        names(&by_name),
+        vec!["Alpha", "alps", "Beta", "delta", "Gamma"]
+    );
+    assert_eq!(by_name.next_cursor, None);
+
+    let by_creation = list(ChannelListInput {
+        sort: Some(ChannelSort::CreatedAt),
+        descending: Some(true),
+        ..general()
+    })
+    .await;
+    assert_eq!(
+        names(&by_creation),
+        vec!["Beta", "delta", "Alpha", "alps", "Gamma"]
+    );
+
+    let prefixed = list(ChannelListInput {
+        sort: Some(ChannelSort::Name),
+        name_prefix: Some("AL".into()),
+        ..general()
+    })
+    .await;
+    assert_eq!(names(&prefixed), vec!["Alpha", "alps"]);
+
+    let bobbos = list(ChannelListInput {
+        created_by: Some(bobbo_cell.agent_pubkey().clone()),
+        ..general()
+    })
+    .await;
+    assert_eq!(names(&bobbos), vec!["Beta"]);
+
+    // Walk through the pages
+    let mut paged = Vec::new();
+    let mut cursor = None;
+    loop {
+        let page = list(ChannelListInput {
+            sort: Some(ChannelSort::Name),
+            limit: Some(2),
+            cursor: cursor.clone(),
+            ..general()
+        })
+        .await;
+        assert!(page.channels.len() <= 2);
+        paged.extend(names(&page));
+        cursor = page.next_cursor;
+        if cursor.is_none() {
+            break;
+        }
+    }
+    assert_eq!(paged, names(&by_name));
+
+    // Pages without a sort still cover every channel once
+    let mut paged = Vec::new();
+    let mut cursor = None;
+    loop {
+        let page = list(ChannelListInput {
+            limit: Some(2),
+            cursor: cursor.clone(),
+            ..general()
+        })
+        .await;
+        paged.extend(names(&page));
+        cursor = page.next_cursor;
+        if cursor.is_none() {
+            break;
+        }
+    }
+    paged.sort_by_key(|name| name.to_lowercase());
+    assert_eq!(paged, names(&by_name));
+
+    // A message makes the oldest channel the most active
+    let _: MessageData = conductor
+        .call(
+            bobbo_chat,
+            "create_message",
+            MessageInput {
+                last_seen: LastSeen::First,
+                channel: gamma.entry.clone(),
+                entry: Message {
+                    uuid: uuid::Uuid::new_v4().to_string(),
+                    content: "Hello".into(),
+                },
+                signal: None,
+            },
+        )
+        .await;
+
+    // TODO: add consistency awaiting to sweettest
+    tokio::time::sleep(tokio::time::Duration::from_millis(4000)).await;
+
+    let by_activity = list(ChannelListInput {
+        sort: Some(ChannelSort::LastActivity),
+        descending: Some(true),
+        ..general()
+    })
+    .await;
+    assert_eq!(
+        names(&by_activity),
+        vec!["Gamma", "Beta", "delta", "Alpha", "alps"]
+    );
+
+    let bad_cursor: Result<ChannelList, _> = conductor
+        .call_fallible(
+            alice_chat,
+            "list_channels",
+            ChannelListInput {
+                cursor: Some("nonsense".into()),
+                ..general()
+            },
+        )
+        .await;
+    assert!(bad_cursor.is_err());
+}
@@ -0,0 +1,64 @@
+use chat::*;
+use hdk::hash_path::path::Component;
+
+#[test]
+fn channel_path_round_trip() {
+    let channel = Channel {
+        category: "General".into(),
+        uuid: uuid::Uuid::new_v4().to_string(),
+    };
+    let path = Path::try_from(channel.clone()).unwrap();
+    assert_eq!(Channel::try_from(&path).unwrap(), channel);
+}
+
+#[test]
+fn channel_with_bad_uuid_has_no_path() {
+    let channel = Channel {
+        category: "General".into(),
+        uuid: "123".into(),
+    };
+    assert!(matches!(
+        Path::try_from(channel),
+        Err(ChatError::InvalidChannelUuid(_))
+    ));
+}
+
+#[test]
+fn malformed_path_is_not_a_channel() {
+    let too_short = Path::from(vec![Component::from(b"General".to_vec())]);
+    assert!(matches!(
+        Channel::try_from(&too_short),
+        Err(ChatError::InvalidChannelPath)
+    ));
+
+    let bad_uuid = Path::from(vec![
+        Component::from(b"General".to_vec()),
+        Component::from(vec![1, 2, 3]),
+    ]);
+    assert!(matches!(
+        Channel::try_from(&bad_uuid),
+        Err(ChatError::InvalidChannelPath)
+    ));
+
+    let bad_category = Path::from(vec![
+        Component::from(vec![0xff, 0xfe]),
+        Component::from(vec![0; 16]),
+    ]);
+    assert!(matches!(
+        Channel::try_from(&bad_category),
+        Err(ChatError::InvalidChannelPath)
+    ));
+}
+
+#[test]
+fn owned_uuid_names_its_owner() {
+    let alice = AgentPubKey::from_raw_36(vec![1; 36]);
+    let bobbo = AgentPubKey::from_raw_36(vec![2; 36]);
+    let channel = Channel {
+        category: "General".into(),
+        uuid: Channel::owned_uuid(&alice, uuid::Uuid::new_v4().as_bytes()),
+    };
+    assert!(Path::try_from(channel.clone()).is_ok());
+    assert!(channel.is_owned_by(&alice));
+    assert!(!channel.is_owned_by(&bobbo));
+}
@@ -0,0 +1,117 @@
+use chat::message::MESSAGE_PREVIEW_LENGTH;
+use chat::*;
+use hc_joining_code::Props;
+use holochain::sweettest::*;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn list_channels_previews_the_last_message() {
+    // Use prebuilt DNA bundle.
+    // You must build the DNA bundle as a separate step before running the test.
+    let dna_path = std::env::current_dir()
+        .unwrap()
+        .join("../../elemental-chat.dna");
+
+    let dna = SweetDnaFile::from_bundle_with_overrides(
+        &dna_path,
+        None,
+        Some(Props {
+            skip_proof: true,
+            holo_agent_override: None,
+            development_stage: None,
+            t_and_c: None,
+            t_and_c_agreement: None,
+        }),
+    )
+    .await
+    .unwrap();
+
+    let mut conductor = SweetConductor::from_standard_config().await;
+    let agents = SweetAgents::get(conductor.keystore(), 2).await;
+    let apps = conductor
+        .setup_app_for_agents("elemental-chat", &agents, &[dna])
+        .await
+        .unwrap();
+    let ((alice_cell,), (bobbo_cell,)) = apps.into_tuples();
+    let alice_chat = &alice_cell.zome("chat");
+    let bobbo_chat = &bobbo_cell.zome("chat");
+
+    let create_channel = |name: &str| ChannelInput {
+        name: name.into(),
+        entry: Channel {
+            category: "General".into(),
+            uuid: Channel::owned_uuid(alice_cell.agent_pubkey(), uuid::Uuid::new_v4().as_bytes()),
+        },
+    };
+    let busy: ChannelData = conductor
+        .call(alice_chat, "create_channel", create_channel("Busy"))
+        .await;
+    let quiet: ChannelData = conductor
+        .call(alice_chat, "create_channel", create_channel("Quiet"))
+        .await;
+
+    let message = |content: String| MessageInput {
+        last_seen: LastSeen::First,
+        channel: busy.entry.clone(),
+        entry: Message {
+            uuid: uuid::Uuid::new_v4().to_string(),
+            content,
+        },
+        signal: None,
+    };
+    let _: MessageData = conductor
+        .call(alice_chat, "create_message", message("first".into()))
+        .await;
+    let long_content: String = std::iter::repeat('x')
+        .take(MESSAGE_PREVIEW_LENGTH + 20)
+        .collect();
+    let last: MessageData = conductor
+        .call(alice_chat, "create_message", message(long_content.clone()))
+        .await;
+
+    // TODO: add consistency awaiting to sweettest
+    tokio::time::sleep(tokio::time::Duration::from_millis(4000)).await;
+
+    let list: ChannelList = conductor
+        .call(
+            bobbo_chat,
+            "list_channels",
+            ChannelListInput {
+                category: Some("General".into()),
+                include_preview: Some(true),
+                ..Default::default()
+            },
+        )
+        .await;
+    let find = |channel: &ChannelData| {
+        list.channels
+            .iter()
+            .find(|c| c.entry == channel.entry)
+            .unwrap()
+            .clone()
+    };
+
+    assert_eq!(find(&quiet).last_message, None);
+    let preview = find(&busy).last_message.unwrap();
+    assert_eq!(preview.entry_hash, last.entry_hash);
+    assert_eq!(preview.created_by, last.created_by);
+    assert_eq!(preview.created_at, last.created_at);
+    assert!(preview.truncated);
+    assert_eq!(preview.content, long_content[..MESSAGE_PREVIEW_LENGTH]);
+
+    // Previews and roles are only read when asked for
+    let plain: ChannelList = conductor
+        .call(
+            bobbo_chat,
+            "list_channels",
+            ChannelListInput {
+                category: Some("General".into()),
+                ..Default::default()
+            },
+        )
+        .await;
+    assert_eq!(plain.channels.len(), 2);
+    assert!(plain
+        .channels
+        .iter()
+        .all(|c| c.last_message.is_none() && c.roles.is_none()));
+}
@@ -0,0 +1,118 @@
+use std::time::Instant;
+
+use chat::message::handlers::{FakeMessage, InsertFakeMessagesPayload};
+use chat::*;
+use hc_joining_code::Props;
+use holochain::sweettest::*;
+
+const NUM_MESSAGES: usize = 50;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn create_messages_in_one_call() {
+    // Use prebuilt DNA bundle.
+    // You must build the DNA bundle as a separate step before running the test.
+    let dna_path = std::env::current_dir()
+        .unwrap()
+        .join("../../elemental-chat.dna");
+
+    let dna = SweetDnaFile::from_bundle_with_overrides(
+        &dna_path,
+        None,
+        Some(Props {
+            skip_proof: true,
+            holo_agent_override: None,
+            development_stage: None,
+            t_and_c: None,
+            t_and_c_agreement: None,
+        }),
+    )
+    .await
+    .unwrap();
+
+    let mut conductor = SweetConductor::from_standard_config().await;
+    let agents = SweetAgents::get(conductor.keystore(), 1).await;
+    let apps = conductor
+        .setup_app_for_agents("elemental-chat", &agents, &[dna])
+        .await
+        .unwrap();
+    let ((alice_cell,),) = apps.into_tuples();
+    let alice_chat = &alice_cell.zome("chat");
+
+    let create_channel = |name: &str| ChannelInput {
+        name: name.into(),
+        entry: Channel {
+            category: "General".into(),
+            uuid: Channel::owned_uuid(alice_cell.agent_pubkey(), uuid::Uuid::new_v4().as_bytes()),
+        },
+    };
+    let looped_channel: ChannelData = conductor
+        .call(alice_chat, "create_channel", create_channel("Looped"))
+        .await;
+    let batched_channel: ChannelData = conductor
+        .call(alice_chat, "create_channel", create_channel("Batched"))
+        .await;
+
+    // The current way: one create per message inside a single call
+    let now = Timestamp::now();
+    let start = Instant::now();
+    let _: () = conductor
+        .call(
+            alice_chat,
+            "insert_fake_messages",
+            InsertFakeMessagesPayload {
+                messages: (0..NUM_MESSAGES)
+                    .map(|i| FakeMessage {
+                        content: format!("looped {}", i),
+                        timestamp: now,
+                    })
+                    .collect(),
+                channel: looped_channel.entry.clone(),
+            },
+        )
+        .await;
+    let looped = start.elapsed();
+
+    let inputs: Vec<MessageInput> = (0..NUM_MESSAGES)
+        .map(|i| MessageInput {
+            last_seen: LastSeen::First,
+            channel: batched_channel.entry.clone(),
+            entry: Message {
+                uuid: uuid::Uuid::new_v4().to_string(),
+                content: format!("batched {}", i),
+            },
+            signal: None,
+        })
+        .collect();
+    let start = Instant::now();
+    let created: Vec<MessageData> = conductor
+        .call(alice_chat, "create_messages", inputs.clone())
+        .await;
+    let batched = start.elapsed();
+    println!(
+        "{} messages: looped {:?}, batched {:?}",
+        NUM_MESSAGES, looped, batched
+    );
+
+    // Returned in the order given
+    let contents: Vec<_> = created.iter().map(|m| m.entry.clone()).collect();
+    let expected: Vec<_> = inputs.iter().map(|i| i.entry.clone()).collect();
+    assert_eq!(contents, expected);
+
+    // Retrying the whole batch creates nothing new
+    let retried: Vec<MessageData> = conductor.call(alice_chat, "create_messages", inputs).await;
+    assert_eq!(retried, created);
+
+    let listed: ListMessages = conductor
+        .call(
+            alice_chat,
+            "list_messages",
+            ListMessagesInput {
+                channel: batched_channel.entry,
+                earliest_seen: None,
+                target_message_count: NUM_MESSAGES * 2,
+                include_hidden: None,
+            },
+        )
+        .await;
+    assert_eq!(listed.messages.len(), NUM_MESSAGES);
+}
@@ -0,0 +1,26 @@
+use chat::*;
+
+#[test]
+fn errors_serialize_to_envelope() {
+    let error: WasmError = ChatError::ReadOnly.into();
+    let guest = match error {
+        WasmError::Guest(guest) => guest,
+        e => panic!("expected a guest error, got {:?}", e),
+    };
+    assert_eq!(
+        ChatErrorEnvelope::from_guest(&guest),
+        Some(ChatErrorEnvelope {
+            code: ChatErrorCode::ReadOnly,
+            message: "Read only instance".into(),
+            details: None,
+        })
+    );
+    assert!(guest.contains("\"READ_ONLY\""));
+}
+
+#[test]
+fn errors_carry_details() {
+    let envelope = ChatErrorEnvelope::from(&ChatError::InvalidChannelUuid("123".into()));
+    assert_eq!(envelope.code, ChatErrorCode::InvalidChannelUuid);
+    assert_eq!(envelope.details, Some("123".into()));
+}
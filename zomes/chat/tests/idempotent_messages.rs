@@ -0,0 +1,95 @@
+use chat::*;
+use hc_joining_code::Props;
+use holochain::sweettest::*;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn create_message_is_idempotent_by_uuid() {
+    // Use prebuilt DNA bundle.
+    // You must build the DNA bundle as a separate step before running the test.
+    let dna_path = std::env::current_dir()
+        .unwrap()
+        .join("../../elemental-chat.dna");
+
+    let dna = SweetDnaFile::from_bundle_with_overrides(
+        &dna_path,
+        None,
+        Some(Props {
+            skip_proof: true,
+            holo_agent_override: None,
+            development_stage: None,
+            t_and_c: None,
+            t_and_c_agreement: None,
+        }),
+    )
+    .await
+    .unwrap();
+
+    let mut conductor = SweetConductor::from_standard_config().await;
+    let agents = SweetAgents::get(conductor.keystore(), 1).await;
+    let apps = conductor
+        .setup_app_for_agents("elemental-chat", &agents, &[dna])
+        .await
+        .unwrap();
+    let ((alice_cell,),) = apps.into_tuples();
+    let alice_chat = &alice_cell.zome("chat");
+
+    let create_channel = |name: &str| ChannelInput {
+        name: name.into(),
+        entry: Channel {
+            category: "General".into(),
+            uuid: Channel::owned_uuid(alice_cell.agent_pubkey(), uuid::Uuid::new_v4().as_bytes()),
+        },
+    };
+    let first_channel: ChannelData = conductor
+        .call(alice_chat, "create_channel", create_channel("First"))
+        .await;
+    let second_channel: ChannelData = conductor
+        .call(alice_chat, "create_channel", create_channel("Second"))
+        .await;
+
+    let message_uuid = uuid::Uuid::new_v4().to_string();
+    let message = |channel: &ChannelData, content: &str| MessageInput {
+        last_seen: LastSeen::First,
+        channel: channel.entry.clone(),
+        entry: Message {
+            uuid: message_uuid.clone(),
+            content: content.into(),
+        },
+        signal: None,
+    };
+
+    let created: MessageData = conductor
+        .call(alice_chat, "create_message", message(&first_channel, "hi"))
+        .await;
+
+    // A retry returns the message we already have
+    let retried: MessageData = conductor
+        .call(alice_chat, "create_message", message(&first_channel, "hi"))
+        .await;
+    assert_eq!(retried, created);
+    let edited: MessageData = conductor
+        .call(alice_chat, "create_message", message(&first_channel, "hi!"))
+        .await;
+    assert_eq!(edited, created);
+
+    // The same uuid on another channel is another message
+    let elsewhere: MessageData = conductor
+        .call(alice_chat, "create_message", message(&second_channel, "hi"))
+        .await;
+    assert_ne!(elsewhere.created_at, created.created_at);
+
+    let list = |channel: &ChannelData| ListMessagesInput {
+        channel: channel.entry.clone(),
+        earliest_seen: None,
+        target_message_count: 10,
+        include_hidden: None,
+    };
+    let first: ListMessages = conductor
+        .call(alice_chat, "list_messages", list(&first_channel))
+        .await;
+    assert_eq!(first.messages, vec![created]);
+    let second: ListMessages = conductor
+        .call(alice_chat, "list_messages", list(&second_channel))
+        .await;
+    assert_eq!(second.messages, vec![elsewhere]);
+}
@@ -0,0 +1,207 @@
+use std::time::{Duration, Instant};
+
+use chat::*;
+use hc_joining_code::Props;
+use holochain::sweettest::*;
+
+const SAMPLE_MESSAGES: usize = 10;
+const CHAIN_GROWTH_BATCHES: usize = 10;
+const BATCH_SIZE: usize = 100;
+
+/// Mirrors the zome's `AgentStats`, whose fields aren't public
+#[derive(Debug, PartialEq, Deserialize)]
+struct Stats {
+    agents: usize,
+    active: usize,
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn create_message_time_does_not_grow_with_chain() {
+    // Use prebuilt DNA bundle.
+    // You must build the DNA bundle as a separate step before running the test.
+    let dna_path = std::env::current_dir()
+        .unwrap()
+        .join("../../elemental-chat.dna");
+
+    let dna = SweetDnaFile::from_bundle_with_overrides(
+        &dna_path,
+        None,
+        Some(Props {
+            skip_proof: true,
+            holo_agent_override: None,
+            development_stage: None,
+            t_and_c: None,
+            t_and_c_agreement: None,
+        }),
+    )
+    .await
+    .unwrap();
+
+    let mut conductor = SweetConductor::from_standard_config().await;
+    let agents = SweetAgents::get(conductor.keystore(), 1).await;
+    let apps = conductor
+        .setup_app_for_agents("elemental-chat", &agents, &[dna])
+        .await
+        .unwrap();
+    let ((alice_cell,),) = apps.into_tuples();
+    let alice_chat = &alice_cell.zome("chat");
+
+    let channel: ChannelData = conductor
+        .call(
+            alice_chat,
+            "create_channel",
+            ChannelInput {
+                name: "Test Ch".into(),
+                entry: Channel {
+                    category: "General".into(),
+                    uuid: Channel::owned_uuid(
+                        alice_cell.agent_pubkey(),
+                        uuid::Uuid::new_v4().as_bytes(),
+                    ),
+                },
+            },
+        )
+        .await;
+    let short_chain = time_creates(&conductor, alice_chat, &channel.entry, "short").await;
+
+    for batch in 0..CHAIN_GROWTH_BATCHES {
+        let inputs: Vec<MessageInput> = (0..BATCH_SIZE)
+            .map(|i| message(&channel.entry, format!("filler {} {}", batch, i)))
+            .collect();
+        let _: Vec<MessageData> = conductor.call(alice_chat, "create_messages", inputs).await;
+    }
+
+    let long_chain = time_creates(&conductor, alice_chat, &channel.entry, "long").await;
+    println!(
+        "create_message: {:?} on a short chain, {:?} after {} more messages",
+        short_chain,
+        long_chain,
+        CHAIN_GROWTH_BATCHES * BATCH_SIZE
+    );
+    // Generous, so a busy machine doesn't fail the test,
+    // but far below the slowdown of scanning a chain a hundred times longer
+    assert!(long_chain < short_chain * 3 + Duration::from_millis(50));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn refresh_chatter_works_on_a_long_chain() {
+    // Use prebuilt DNA bundle.
+    // You must build the DNA bundle as a separate step before running the test.
+    let dna_path = std::env::current_dir()
+        .unwrap()
+        .join("../../elemental-chat.dna");
+
+    let dna = SweetDnaFile::from_bundle_with_overrides(
+        &dna_path,
+        None,
+        Some(Props {
+            skip_proof: true,
+            holo_agent_override: None,
+            development_stage: None,
+            t_and_c: None,
+            t_and_c_agreement: None,
+        }),
+    )
+    .await
+    .unwrap();
+
+    let mut conductor = SweetConductor::from_standard_config().await;
+    let agents = SweetAgents::get(conductor.keystore(), 1).await;
+    let apps = conductor
+        .setup_app_for_agents("elemental-chat", &agents, &[dna])
+        .await
+        .unwrap();
+    let ((alice_cell,),) = apps.into_tuples();
+    let alice_chat = &alice_cell.zome("chat");
+
+    let channel: ChannelData = conductor
+        .call(
+            alice_chat,
+            "create_channel",
+            ChannelInput {
+                name: "Test Ch".into(),
+                entry: Channel {
+                    category: "General".into(),
+                    uuid: Channel::owned_uuid(
+                        alice_cell.agent_pubkey(),
+                        uuid::Uuid::new_v4().as_bytes(),
+                    ),
+                },
+            },
+        )
+        .await;
+
+    // The first refresh links us on the chatters path, the rest find the recent refresh
+    let _: () = conductor.call(alice_chat, "refresh_chatter", ()).await;
+    let short_chain = time_refreshes(&conductor, alice_chat).await;
+
+    // Lots of links on our chain, none of them on the chatters path
+    for batch in 0..CHAIN_GROWTH_BATCHES {
+        let inputs: Vec<MessageInput> = (0..BATCH_SIZE)
+            .map(|i| message(&channel.entry, format!("filler {} {}", batch, i)))
+            .collect();
+        let _: Vec<MessageData> = conductor.call(alice_chat, "create_messages", inputs).await;
+    }
+
+    let long_chain = time_refreshes(&conductor, alice_chat).await;
+    println!(
+        "refresh_chatter: {:?} on a short chain, {:?} after {} more messages",
+        short_chain,
+        long_chain,
+        CHAIN_GROWTH_BATCHES * BATCH_SIZE
+    );
+
+    // TODO: add consistency awaiting to sweettest
+    tokio::time::sleep(tokio::time::Duration::from_millis(4000)).await;
+
+    // Still exactly one active chatter
+    let stats: Stats = conductor.call(alice_chat, "agent_stats", ()).await;
+    assert_eq!(
+        stats,
+        Stats {
+            agents: 1,
+            active: 1
+        }
+    );
+}
+
+fn message(channel: &Channel, content: String) -> MessageInput {
+    MessageInput {
+        last_seen: LastSeen::First,
+        channel: channel.clone(),
+        entry: Message {
+            uuid: uuid::Uuid::new_v4().to_string(),
+            content,
+        },
+        signal: None,
+    }
+}
+
+/// The average time of a `create_message` call
+async fn time_creates(
+    conductor: &SweetConductor,
+    zome: &SweetZome,
+    channel: &Channel,
+    label: &str,
+) -> Duration {
+    let start = Instant::now();
+    for i in 0..SAMPLE_MESSAGES {
+        let _: MessageData = conductor
+            .call(
+                zome,
+                "create_message",
+                message(channel, format!("{} {}", label, i)),
+            )
+            .await;
+    }
+    start.elapsed() / SAMPLE_MESSAGES as u32
+}
+
+/// The average time of a `refresh_chatter` call
+async fn time_refreshes(conductor: &SweetConductor, zome: &SweetZome) -> Duration {
+    let start = Instant::now();
+    for _ in 0..SAMPLE_MESSAGES {
+        let _: () = conductor.call(zome, "refresh_chatter", ()).await;
+    }
+    start.elapsed() / SAMPLE_MESSAGES as u32
+}
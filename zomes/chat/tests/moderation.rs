@@ -0,0 +1,181 @@
+use chat::*;
+use hc_joining_code::Props;
+use holochain::conductor::api::error::ConductorApiResult;
+use holochain::sweettest::*;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn moderators_hide_messages_and_the_log_records_it() {
+    // Use prebuilt DNA bundle.
+    // You must build the DNA bundle as a separate step before running the test.
+    let dna_path = std::env::current_dir()
+        .unwrap()
+        .join("../../elemental-chat.dna");
+
+    let dna = SweetDnaFile::from_bundle_with_overrides(
+        &dna_path,
+        None,
+        Some(Props {
+            skip_proof: true,
+            holo_agent_override: None,
+            development_stage: None,
+            t_and_c: None,
+            t_and_c_agreement: None,
+        }),
+    )
+    .await
+    .unwrap();
+
+    let mut conductor = SweetConductor::from_standard_config().await;
+    let agents = SweetAgents::get(conductor.keystore(), 3).await;
+    let apps = conductor
+        .setup_app_for_agents("elemental-chat", &agents, &[dna])
+        .await
+        .unwrap();
+    let ((alice_cell,), (bobbo_cell,), (carol_cell,)) = apps.into_tuples();
+    let alice_chat = &alice_cell.zome("chat");
+    let bobbo_chat = &bobbo_cell.zome("chat");
+    let carol_chat = &carol_cell.zome("chat");
+    let carol = carol_cell.agent_pubkey().clone();
+
+    let channel = Channel {
+        category: "General".into(),
+        uuid: Channel::owned_uuid(alice_cell.agent_pubkey(), uuid::Uuid::new_v4().as_bytes()),
+    };
+    let _: ChannelData = conductor
+        .call(
+            alice_chat,
+            "create_channel",
+            ChannelInput {
+                name: "Moderated".into(),
+                entry: channel.clone(),
+            },
+        )
+        .await;
+    let _: () = conductor
+        .call(
+            alice_chat,
+            "grant_role",
+            RoleInput {
+                channel: channel.clone(),
+                agent: carol.clone(),
+                role: ChannelRole::Moderator,
+            },
+        )
+        .await;
+
+    let message = |content: &str| MessageInput {
+        last_seen: LastSeen::First,
+        channel: channel.clone(),
+        entry: Message {
+            uuid: uuid::Uuid::new_v4().to_string(),
+            content: content.into(),
+        },
+        signal: None,
+    };
+    let kind: CreatedMessage = conductor
+        .call(bobbo_chat, "create_message", message("Hello"))
+        .await;
+    let rude: CreatedMessage = conductor
+        .call(bobbo_chat, "create_message", message("Something rude"))
+        .await;
+
+    // TODO: add consistency awaiting to sweettest
+    tokio::time::sleep(tokio::time::Duration::from_millis(4000)).await;
+
+    let hide = |message: &CreatedMessage| HideMessageInput {
+        channel: channel.clone(),
+        message_hash: message.message.entry_hash.clone(),
+        reason: "Rude".into(),
+    };
+    // Only moderators can hide messages
+    let not_moderator: ConductorApiResult<ModerationAction> = conductor
+        .call_fallible(bobbo_chat, "hide_message", hide(&kind))
+        .await;
+    assert!(not_moderator.is_err());
+
+    let action: ModerationAction = conductor
+        .call(carol_chat, "hide_message", hide(&rude))
+        .await;
+    assert_eq!(
+        action.kind,
+        ModerationKind::HideMessage(rude.message.entry_hash.clone())
+    );
+    assert_eq!(action.moderator, carol);
+
+    tokio::time::sleep(tokio::time::Duration::from_millis(4000)).await;
+
+    let list = |include_hidden| ListMessagesInput {
+        channel: channel.clone(),
+        earliest_seen: None,
+        target_message_count: 10,
+        include_hidden,
+    };
+    let visible: ListMessages = conductor
+        .call(bobbo_chat, "list_messages", list(None))
+        .await;
+    assert_eq!(visible.messages, vec![kind.message.clone()]);
+
+    // Only moderators can see hidden messages
+    let peek: ConductorApiResult<ListMessages> = conductor
+        .call_fallible(bobbo_chat, "list_messages", list(Some(true)))
+        .await;
+    assert!(peek.is_err());
+
+    let all: ListMessages = conductor
+        .call(carol_chat, "list_messages", list(Some(true)))
+        .await;
+    assert_eq!(all.messages.len(), 2);
+    assert!(!all.messages[0].hidden);
+    assert!(all.messages[1].hidden);
+    assert_eq!(all.messages[1].entry_hash, rude.message.entry_hash);
+
+    let log: ModerationLog = conductor
+        .call(
+            bobbo_chat,
+            "get_moderation_log",
+            ModerationLogInput {
+                channel: channel.clone(),
+                moderator: None,
+                since: None,
+            },
+        )
+        .await;
+    assert_eq!(log.actions, vec![action.clone()]);
+
+    let _: () = conductor
+        .call(
+            alice_chat,
+            "revoke_role",
+            RoleInput {
+                channel: channel.clone(),
+                agent: carol.clone(),
+                role: ChannelRole::Moderator,
+            },
+        )
+        .await;
+
+    tokio::time::sleep(tokio::time::Duration::from_millis(4000)).await;
+
+    // A revoked moderator can't hide any more,
+    // but what they hid while they were a moderator stays hidden
+    let revoked: ConductorApiResult<ModerationAction> = conductor
+        .call_fallible(carol_chat, "hide_message", hide(&kind))
+        .await;
+    assert!(revoked.is_err());
+    let visible: ListMessages = conductor
+        .call(bobbo_chat, "list_messages", list(None))
+        .await;
+    assert_eq!(visible.messages, vec![kind.message]);
+    let log: ModerationLog = conductor
+        .call(
+            bobbo_chat,
+            "get_moderation_log",
+            ModerationLogInput {
+                channel: channel.clone(),
+                moderator: None,
+                since: None,
+            },
+        )
+        .await;
+    assert_eq!(log.actions, vec![action]);
+}
@@ -0,0 +1,127 @@
+use chat::*;
+use hc_joining_code::Props;
+use holochain::sweettest::*;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn outbox_confirms_messages() {
+    // Use prebuilt DNA bundle.
+    // You must build the DNA bundle as a separate step before running the test.
+    let dna_path = std::env::current_dir()
+        .unwrap()
+        .join("../../elemental-chat.dna");
+
+    let dna = SweetDnaFile::from_bundle_with_overrides(
+        &dna_path,
+        None,
+        Some(Props {
+            skip_proof: true,
+            holo_agent_override: None,
+            development_stage: None,
+            t_and_c: None,
+            t_and_c_agreement: None,
+        }),
+    )
+    .await
+    .unwrap();
+
+    let mut conductor = SweetConductor::from_standard_config().await;
+    let agents = SweetAgents::get(conductor.keystore(), 1).await;
+    let apps = conductor
+        .setup_app_for_agents("elemental-chat", &agents, &[dna])
+        .await
+        .unwrap();
+    let ((alice_cell,),) = apps.into_tuples();
+    let alice_chat = &alice_cell.zome("chat");
+
+    let channel: ChannelData = conductor
+        .call(
+            alice_chat,
+            "create_channel",
+            ChannelInput {
+                name: "Test Ch".into(),
+                entry: Channel {
+                    category: "General".into(),
+                    uuid: Channel::owned_uuid(
+                        alice_cell.agent_pubkey(),
+                        uuid::Uuid::new_v4().as_bytes(),
+                    ),
+                },
+            },
+        )
+        .await;
+
+    let message = |channel: &Channel, content: &str| MessageInput {
+        last_seen: LastSeen::First,
+        channel: channel.clone(),
+        entry: Message {
+            uuid: uuid::Uuid::new_v4().to_string(),
+            content: content.into(),
+        },
+        signal: Some(true),
+    };
+    let created: CreatedMessage = conductor
+        .call(
+            alice_chat,
+            "create_message",
+            message(&channel.entry, "Hello from alice :)"),
+        )
+        .await;
+    assert!(created.sig_results.is_some());
+
+    // A channel without an info yet can't be signalled, so the message needs another try
+    let unsignalled_channel = Channel {
+        category: "General".into(),
+        uuid: Channel::owned_uuid(alice_cell.agent_pubkey(), uuid::Uuid::new_v4().as_bytes()),
+    };
+    let unsignalled: CreatedMessage = conductor
+        .call(
+            alice_chat,
+            "create_message",
+            message(&unsignalled_channel, "Anyone there?"),
+        )
+        .await;
+    assert!(unsignalled.sig_results.is_none());
+
+    let pending: PendingMessages = conductor
+        .call(alice_chat, "list_pending_messages", ())
+        .await;
+    let needs_signal: Vec<_> = pending
+        .messages
+        .iter()
+        .filter(|record| record.needs_signal)
+        .map(|record| (record.message.clone(), record.channel.clone()))
+        .collect();
+    assert_eq!(
+        needs_signal,
+        vec![(unsignalled.message.clone(), unsignalled_channel.clone())]
+    );
+
+    // Still no info, so the retry fails to signal again
+    let still_pending: PendingMessages = conductor.call(alice_chat, "retry_pending", ()).await;
+    assert!(still_pending
+        .messages
+        .iter()
+        .any(|record| record.message == unsignalled.message && record.needs_signal));
+
+    let _: ChannelData = conductor
+        .call(
+            alice_chat,
+            "create_channel",
+            ChannelInput {
+                name: "Late".into(),
+                entry: unsignalled_channel,
+            },
+        )
+        .await;
+
+    // TODO: add consistency awaiting to sweettest
+    tokio::time::sleep(tokio::time::Duration::from_millis(4000)).await;
+
+    // Now the signal goes out and the network has seen both messages
+    let still_pending: PendingMessages = conductor.call(alice_chat, "retry_pending", ()).await;
+    assert!(still_pending.messages.is_empty());
+    let pending: PendingMessages = conductor
+        .call(alice_chat, "list_pending_messages", ())
+        .await;
+    assert!(pending.messages.is_empty());
+}
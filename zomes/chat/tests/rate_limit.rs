@@ -0,0 +1,161 @@
+use chat::*;
+use hc_joining_code::Props;
+use holochain::conductor::api::error::ConductorApiResult;
+use holochain::sweettest::*;
+
+/// The usual test properties with rate limits added
+#[derive(Serialize)]
+struct RateLimitedProps {
+    #[serde(flatten)]
+    props: Props,
+    #[serde(flatten)]
+    rate_limit: RateLimitConfig,
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn validation_rejects_messages_over_the_rate_limits() {
+    // Use prebuilt DNA bundle.
+    // You must build the DNA bundle as a separate step before running the test.
+    let dna_path = std::env::current_dir()
+        .unwrap()
+        .join("../../elemental-chat.dna");
+
+    let dna = SweetDnaFile::from_bundle_with_overrides(
+        &dna_path,
+        None,
+        Some(RateLimitedProps {
+            props: Props {
+                skip_proof: true,
+                holo_agent_override: None,
+                development_stage: None,
+                t_and_c: None,
+                t_and_c_agreement: None,
+            },
+            rate_limit: RateLimitConfig {
+                max_messages_per_minute: Some(5),
+                slow_mode_seconds: None,
+            },
+        }),
+    )
+    .await
+    .unwrap();
+
+    let mut conductor = SweetConductor::from_standard_config().await;
+    let agents = SweetAgents::get(conductor.keystore(), 2).await;
+    let apps = conductor
+        .setup_app_for_agents("elemental-chat", &agents, &[dna])
+        .await
+        .unwrap();
+    let ((alice_cell,), (bobbo_cell,)) = apps.into_tuples();
+    let alice_chat = &alice_cell.zome("chat");
+    let bobbo_chat = &bobbo_cell.zome("chat");
+
+    let slow_channel = Channel {
+        category: "General".into(),
+        uuid: Channel::owned_uuid(alice_cell.agent_pubkey(), uuid::Uuid::new_v4().as_bytes()),
+    };
+    let busy_channel = Channel {
+        category: "General".into(),
+        uuid: Channel::owned_uuid(alice_cell.agent_pubkey(), uuid::Uuid::new_v4().as_bytes()),
+    };
+
+    for (name, channel) in [("Slow", &slow_channel), ("Busy", &busy_channel)] {
+        let _: ChannelData = conductor
+            .call(
+                alice_chat,
+                "create_channel",
+                ChannelInput {
+                    name: name.into(),
+                    entry: channel.clone(),
+                },
+            )
+            .await;
+    }
+    // TODO: add consistency awaiting to sweettest
+    tokio::time::sleep(tokio::time::Duration::from_millis(4000)).await;
+
+    // Only the owner can put a channel in slow mode
+    let slow_mode = SlowModeInput {
+        channel: slow_channel.clone(),
+        seconds: Some(60),
+    };
+    let not_owner: ConductorApiResult<ChannelData> = conductor
+        .call_fallible(bobbo_chat, "set_slow_mode", slow_mode)
+        .await;
+    assert!(not_owner.is_err());
+    let slowed: ChannelData = conductor
+        .call(
+            alice_chat,
+            "set_slow_mode",
+            SlowModeInput {
+                channel: slow_channel.clone(),
+                seconds: Some(60),
+            },
+        )
+        .await;
+    assert_eq!(slowed.info.slow_mode_seconds, Some(60));
+
+    tokio::time::sleep(tokio::time::Duration::from_millis(4000)).await;
+
+    let message = |channel: &Channel| MessageInput {
+        last_seen: LastSeen::First,
+        channel: channel.clone(),
+        entry: Message {
+            uuid: uuid::Uuid::new_v4().to_string(),
+            content: "Hello".into(),
+        },
+        signal: None,
+    };
+
+    // Slow mode allows one message per minute on the slow channel
+    let _: CreatedMessage = conductor
+        .call(alice_chat, "create_message", message(&slow_channel))
+        .await;
+    let too_soon: ConductorApiResult<CreatedMessage> = conductor
+        .call_fallible(alice_chat, "create_message", message(&slow_channel))
+        .await;
+    assert!(too_soon.is_err());
+    // Other channels aren't slowed down
+    let _: CreatedMessage = conductor
+        .call(alice_chat, "create_message", message(&busy_channel))
+        .await;
+    // A batch gets the same early check
+    let batch: ConductorApiResult<Vec<MessageData>> = conductor
+        .call_fallible(alice_chat, "create_messages", vec![message(&slow_channel)])
+        .await;
+    assert!(batch.is_err());
+
+    // A batch that would go over the rate limit part way through commits nothing
+    let batch: ConductorApiResult<Vec<MessageData>> = conductor
+        .call_fallible(
+            alice_chat,
+            "create_messages",
+            (0..5).map(|_| message(&busy_channel)).collect::<Vec<_>>(),
+        )
+        .await;
+    assert!(batch.is_err());
+    // So alice still has room for three more
+    let batch: Vec<MessageData> = conductor
+        .call(
+            alice_chat,
+            "create_messages",
+            (0..3).map(|_| message(&busy_channel)).collect::<Vec<_>>(),
+        )
+        .await;
+    assert_eq!(batch.len(), 3);
+
+    // The rate limit counts messages on every channel
+    for _ in 0..5 {
+        let _: CreatedMessage = conductor
+            .call(bobbo_chat, "create_message", message(&busy_channel))
+            .await;
+    }
+    let flood: ConductorApiResult<CreatedMessage> = conductor
+        .call_fallible(bobbo_chat, "create_message", message(&busy_channel))
+        .await;
+    assert!(flood.is_err());
+    let batch: ConductorApiResult<Vec<MessageData>> = conductor
+        .call_fallible(bobbo_chat, "create_messages", vec![message(&busy_channel)])
+        .await;
+    assert!(batch.is_err());
+}
@@ -0,0 +1,163 @@
+use std::sync::Arc;
+
+use chat::message::handlers::{FakeMessage, InsertFakeMessagesPayload, LinkFakeMessagePayload};
+use chat::*;
+use hc_joining_code::Props;
+use holochain::conductor::api::error::ConductorApiResult;
+use holochain::sweettest::*;
+use holochain_types::prelude::{CellId, InstalledCell, MembraneProof};
+
+#[tokio::test(flavor = "multi_thread")]
+async fn read_only_agent_cannot_commit() {
+    // Use prebuilt DNA bundle.
+    // You must build the DNA bundle as a separate step before running the test.
+    let dna_path = std::env::current_dir()
+        .unwrap()
+        .join("../../elemental-chat.dna");
+
+    let dna = SweetDnaFile::from_bundle_with_overrides(
+        &dna_path,
+        None,
+        Some(Props {
+            skip_proof: true,
+            holo_agent_override: None,
+            development_stage: None,
+            t_and_c: None,
+            t_and_c_agreement: None,
+        }),
+    )
+    .await
+    .unwrap();
+    let dna_hash = dna.dna_hash().clone();
+
+    let mut conductor = SweetConductor::from_standard_config().await;
+    let agents = SweetAgents::get(conductor.keystore(), 1).await;
+    let apps = conductor
+        .setup_app_for_agents("elemental-chat", &agents, &[dna])
+        .await
+        .unwrap();
+    let ((alice_cell,),) = apps.into_tuples();
+    let alice_chat = &alice_cell.zome("chat");
+
+    // Install doug with the read-only membrane proof (a msgpack encoded `0`)
+    let doug = SweetAgents::one(conductor.keystore()).await;
+    let doug_cell_id = CellId::new(dna_hash, doug);
+    let read_only_proof: MembraneProof =
+        Arc::new(SerializedBytes::from(UnsafeBytes::from(vec![0_u8])));
+    conductor
+        .raw_handle()
+        .install_app(
+            "read-only".to_string(),
+            vec![(
+                InstalledCell::new(doug_cell_id.clone(), "elemental-chat".into()),
+                Some(read_only_proof),
+            )],
+        )
+        .await
+        .unwrap();
+    conductor
+        .raw_handle()
+        .enable_app("read-only".to_string())
+        .await
+        .unwrap();
+    let doug_chat = &SweetZome::new(doug_cell_id, "chat".into());
+
+    // Setup complete.
+
+    let channel: ChannelData = conductor
+        .call(
+            alice_chat,
+            "create_channel",
+            ChannelInput {
+                name: "Test Ch".into(),
+                entry: Channel {
+                    category: "General".into(),
+                    uuid: Channel::owned_uuid(
+                        alice_cell.agent_pubkey(),
+                        uuid::Uuid::new_v4().as_bytes(),
+                    ),
+                },
+            },
+        )
+        .await;
+
+    // Reading still works
+    let _: ChannelList = conductor
+        .call(
+            doug_chat,
+            "list_channels",
+            ChannelListInput {
+                category: Some("General".into()),
+                ..Default::default()
+            },
+        )
+        .await;
+
+    // The regular agent is unaffected
+    let _: () = conductor
+        .call(
+            alice_chat,
+            "insert_fake_messages",
+            InsertFakeMessagesPayload {
+                messages: vec![FakeMessage {
+                    content: "hello".into(),
+                    timestamp: Timestamp::now(),
+                }],
+                channel: channel.entry.clone(),
+            },
+        )
+        .await;
+    let list_messages = ListMessagesInput {
+        channel: channel.entry.clone(),
+        earliest_seen: None,
+        target_message_count: 10,
+        include_hidden: None,
+    };
+    let alice_msgs: ListMessages = conductor
+        .call(alice_chat, "list_messages", list_messages.clone())
+        .await;
+    assert_eq!(alice_msgs.messages.len(), 1);
+
+    // `insert_fake_messages` and `link_fake_message` skip the zome-level read-only check,
+    // standing in for a patched zome. Validation must still reject the commits.
+    let assert_read_only = |result: ConductorApiResult<()>| {
+        let error = format!("{:?}", result.expect_err("read-only commit was accepted"));
+        assert!(
+            error.contains(&ChatError::ReadOnly.to_string()),
+            "rejected for another reason: {}",
+            error
+        );
+    };
+    let entry_bypass: ConductorApiResult<()> = conductor
+        .call_fallible(
+            doug_chat,
+            "insert_fake_messages",
+            InsertFakeMessagesPayload {
+                messages: vec![FakeMessage {
+                    content: "sneaky".into(),
+                    timestamp: Timestamp::now(),
+                }],
+                channel: channel.entry.clone(),
+            },
+        )
+        .await;
+    assert_read_only(entry_bypass);
+    // Linking Alice's message commits no entry, so only the link's validation can catch it
+    let link_bypass: ConductorApiResult<()> = conductor
+        .call_fallible(
+            doug_chat,
+            "link_fake_message",
+            LinkFakeMessagePayload {
+                message: alice_msgs.messages[0].entry_hash.clone(),
+                channel: channel.entry.clone(),
+                timestamp: Timestamp::now(),
+            },
+        )
+        .await;
+    assert_read_only(link_bypass);
+
+    let doug_msgs: ListMessages = conductor
+        .call(doug_chat, "list_messages", list_messages)
+        .await;
+    assert_eq!(doug_msgs.messages.len(), 1);
+}
@@ -0,0 +1,137 @@
+use chat::*;
+use hc_joining_code::Props;
+use holochain::conductor::api::error::ConductorApiResult;
+use holochain::sweettest::*;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reports_are_queued_for_the_channel_owner() {
+    // Use prebuilt DNA bundle.
+    // You must build the DNA bundle as a separate step before running the test.
+    let dna_path = std::env::current_dir()
+        .unwrap()
+        .join("../../elemental-chat.dna");
+
+    let dna = SweetDnaFile::from_bundle_with_overrides(
+        &dna_path,
+        None,
+        Some(Props {
+            skip_proof: true,
+            holo_agent_override: None,
+            development_stage: None,
+            t_and_c: None,
+            t_and_c_agreement: None,
+        }),
+    )
+    .await
+    .unwrap();
+
+    let mut conductor = SweetConductor::from_standard_config().await;
+    let agents = SweetAgents::get(conductor.keystore(), 3).await;
+    let apps = conductor
+        .setup_app_for_agents("elemental-chat", &agents, &[dna])
+        .await
+        .unwrap();
+    let ((alice_cell,), (bobbo_cell,), (carol_cell,)) = apps.into_tuples();
+    let alice_chat = &alice_cell.zome("chat");
+    let bobbo_chat = &bobbo_cell.zome("chat");
+    let carol_chat = &carol_cell.zome("chat");
+
+    let channel = Channel {
+        category: "General".into(),
+        uuid: Channel::owned_uuid(alice_cell.agent_pubkey(), uuid::Uuid::new_v4().as_bytes()),
+    };
+    let _: ChannelData = conductor
+        .call(
+            alice_chat,
+            "create_channel",
+            ChannelInput {
+                name: "Reported".into(),
+                entry: channel.clone(),
+            },
+        )
+        .await;
+    let message = |content: &str| MessageInput {
+        last_seen: LastSeen::First,
+        channel: channel.clone(),
+        entry: Message {
+            uuid: uuid::Uuid::new_v4().to_string(),
+            content: content.into(),
+        },
+        signal: None,
+    };
+    let rude: CreatedMessage = conductor
+        .call(bobbo_chat, "create_message", message("Something rude"))
+        .await;
+    let spam: CreatedMessage = conductor
+        .call(bobbo_chat, "create_message", message("Buy now"))
+        .await;
+
+    // TODO: add consistency awaiting to sweettest
+    tokio::time::sleep(tokio::time::Duration::from_millis(4000)).await;
+
+    let report = |message: &CreatedMessage, reason: &str| ReportMessageInput {
+        channel: channel.clone(),
+        message_hash: message.message.entry_hash.clone(),
+        reason: reason.into(),
+    };
+    let rude_report: ReportData = conductor
+        .call(carol_chat, "report_message", report(&rude, "Rude"))
+        .await;
+    assert_eq!(rude_report.status, ReportStatus::Open);
+    let spam_report: ReportData = conductor
+        .call(carol_chat, "report_message", report(&spam, "Spam"))
+        .await;
+    // Each report cites its reporter's previous one
+    assert_eq!(rude_report.report.previous, None);
+    assert!(spam_report.report.previous.is_some());
+
+    // Each agent can only report a message once
+    let again: ConductorApiResult<ReportData> = conductor
+        .call_fallible(carol_chat, "report_message", report(&rude, "Still rude"))
+        .await;
+    assert!(again.is_err());
+
+    tokio::time::sleep(tokio::time::Duration::from_millis(4000)).await;
+
+    let list = |include_closed| ListReportsInput {
+        channel: channel.clone(),
+        include_closed,
+    };
+    // Only the owner can see the reports
+    let not_owner: ConductorApiResult<ReportList> = conductor
+        .call_fallible(carol_chat, "list_reports", list(None))
+        .await;
+    assert!(not_owner.is_err());
+
+    let open: ReportList = conductor.call(alice_chat, "list_reports", list(None)).await;
+    assert_eq!(open.reports, vec![rude_report.clone(), spam_report.clone()]);
+
+    let close = |report: &ReportData| CloseReportInput {
+        channel: channel.clone(),
+        report_hash: report.entry_hash.clone(),
+        note: "Handled".into(),
+    };
+    let not_owner: ConductorApiResult<ModerationAction> = conductor
+        .call_fallible(carol_chat, "resolve_report", close(&rude_report))
+        .await;
+    assert!(not_owner.is_err());
+    let _: ModerationAction = conductor
+        .call(alice_chat, "resolve_report", close(&rude_report))
+        .await;
+    let _: ModerationAction = conductor
+        .call(alice_chat, "dismiss_report", close(&spam_report))
+        .await;
+
+    tokio::time::sleep(tokio::time::Duration::from_millis(4000)).await;
+
+    let open: ReportList = conductor.call(alice_chat, "list_reports", list(None)).await;
+    assert!(open.reports.is_empty());
+    let all: ReportList = conductor
+        .call(alice_chat, "list_reports", list(Some(true)))
+        .await;
+    let statuses: Vec<_> = all.reports.iter().map(|report| report.status).collect();
+    assert_eq!(
+        statuses,
+        vec![ReportStatus::Resolved, ReportStatus::Dismissed]
+    );
+}
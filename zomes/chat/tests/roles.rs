@@ -0,0 +1,171 @@
+use chat::*;
+use hc_joining_code::Props;
+use holochain::conductor::api::error::ConductorApiResult;
+use holochain::sweettest::*;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn roles_are_granted_down_the_chain_of_authority() {
+    // Use prebuilt DNA bundle.
+    // You must build the DNA bundle as a separate step before running the test.
+    let dna_path = std::env::current_dir()
+        .unwrap()
+        .join("../../elemental-chat.dna");
+
+    let dna = SweetDnaFile::from_bundle_with_overrides(
+        &dna_path,
+        None,
+        Some(Props {
+            skip_proof: true,
+            holo_agent_override: None,
+            development_stage: None,
+            t_and_c: None,
+            t_and_c_agreement: None,
+        }),
+    )
+    .await
+    .unwrap();
+
+    let mut conductor = SweetConductor::from_standard_config().await;
+    let agents = SweetAgents::get(conductor.keystore(), 3).await;
+    let apps = conductor
+        .setup_app_for_agents("elemental-chat", &agents, &[dna])
+        .await
+        .unwrap();
+    let ((alice_cell,), (bobbo_cell,), (carol_cell,)) = apps.into_tuples();
+    let alice_chat = &alice_cell.zome("chat");
+    let bobbo_chat = &bobbo_cell.zome("chat");
+    let carol_chat = &carol_cell.zome("chat");
+    let alice = alice_cell.agent_pubkey().clone();
+    let bobbo = bobbo_cell.agent_pubkey().clone();
+    let carol = carol_cell.agent_pubkey().clone();
+
+    let channel = Channel {
+        category: "General".into(),
+        uuid: Channel::owned_uuid(alice_cell.agent_pubkey(), uuid::Uuid::new_v4().as_bytes()),
+    };
+    let _: ChannelData = conductor
+        .call(
+            alice_chat,
+            "create_channel",
+            ChannelInput {
+                name: "Moderated".into(),
+                entry: channel.clone(),
+            },
+        )
+        .await;
+    let role_input = |agent: &AgentPubKey, role| RoleInput {
+        channel: channel.clone(),
+        agent: agent.clone(),
+        role,
+    };
+
+    // TODO: add consistency awaiting to sweettest
+    tokio::time::sleep(tokio::time::Duration::from_millis(4000)).await;
+
+    // The uuid names alice as the owner, so carol can't link a first info of her own onto it
+    let squatted: ConductorApiResult<ChannelData> = conductor
+        .call_fallible(
+            carol_chat,
+            "create_channel",
+            ChannelInput {
+                name: "Mine now".into(),
+                entry: channel.clone(),
+            },
+        )
+        .await;
+    assert!(squatted.is_err());
+
+    // Nobody but the owner can grant admin
+    let stranger: ConductorApiResult<()> = conductor
+        .call_fallible(
+            carol_chat,
+            "grant_role",
+            role_input(&carol, ChannelRole::Admin),
+        )
+        .await;
+    assert!(stranger.is_err());
+
+    let _: () = conductor
+        .call(
+            alice_chat,
+            "grant_role",
+            role_input(&bobbo, ChannelRole::Admin),
+        )
+        .await;
+
+    tokio::time::sleep(tokio::time::Duration::from_millis(4000)).await;
+
+    // An admin can't make more admins but can make moderators
+    let admin: ConductorApiResult<()> = conductor
+        .call_fallible(
+            bobbo_chat,
+            "grant_role",
+            role_input(&carol, ChannelRole::Admin),
+        )
+        .await;
+    assert!(admin.is_err());
+    let _: () = conductor
+        .call(
+            bobbo_chat,
+            "grant_role",
+            role_input(&carol, ChannelRole::Moderator),
+        )
+        .await;
+
+    tokio::time::sleep(tokio::time::Duration::from_millis(4000)).await;
+
+    let roles: ChannelRoles = conductor
+        .call(carol_chat, "get_channel_roles", channel.clone())
+        .await;
+    assert_eq!(
+        roles,
+        ChannelRoles {
+            owner: Some(alice.clone()),
+            admins: vec![bobbo.clone()],
+            moderators: vec![carol.clone()],
+        }
+    );
+
+    // Listed channels carry their roles
+    let list: ChannelList = conductor
+        .call(
+            carol_chat,
+            "list_channels",
+            ChannelListInput {
+                category: Some("General".into()),
+                include_roles: Some(true),
+                ..Default::default()
+            },
+        )
+        .await;
+    assert_eq!(list.channels[0].roles, Some(roles));
+
+    // A moderator can't touch the admin who made them
+    let revolt: ConductorApiResult<()> = conductor
+        .call_fallible(
+            carol_chat,
+            "revoke_role",
+            role_input(&bobbo, ChannelRole::Admin),
+        )
+        .await;
+    assert!(revolt.is_err());
+
+    let _: () = conductor
+        .call(
+            alice_chat,
+            "revoke_role",
+            role_input(&bobbo, ChannelRole::Admin),
+        )
+        .await;
+
+    tokio::time::sleep(tokio::time::Duration::from_millis(4000)).await;
+
+    // Moderators an admin appointed keep their role after the admin loses theirs,
+    // since the grant came before the revocation
+    let roles: ChannelRoles = conductor
+        .call(carol_chat, "get_channel_roles", channel.clone())
+        .await;
+    assert_eq!(roles.owner, Some(alice));
+    assert!(roles.admins.is_empty());
+    assert_eq!(roles.moderators, vec![carol]);
+}
@@ -0,0 +1,123 @@
+use chat::*;
+use hc_joining_code::Props;
+use holochain::sweettest::*;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn sync_since_returns_only_new_data() {
+    // Use prebuilt DNA bundle.
+    // You must build the DNA bundle as a separate step before running the test.
+    let dna_path = std::env::current_dir()
+        .unwrap()
+        .join("../../elemental-chat.dna");
+
+    let dna = SweetDnaFile::from_bundle_with_overrides(
+        &dna_path,
+        None,
+        Some(Props {
+            skip_proof: true,
+            holo_agent_override: None,
+            development_stage: None,
+            t_and_c: None,
+            t_and_c_agreement: None,
+        }),
+    )
+    .await
+    .unwrap();
+
+    let mut conductor = SweetConductor::from_standard_config().await;
+    let agents = SweetAgents::get(conductor.keystore(), 2).await;
+    let apps = conductor
+        .setup_app_for_agents("elemental-chat", &agents, &[dna])
+        .await
+        .unwrap();
+    let ((alice_cell,), (bobbo_cell,)) = apps.into_tuples();
+    let alice_chat = &alice_cell.zome("chat");
+    let bobbo_chat = &bobbo_cell.zome("chat");
+
+    let create_channel = |name: &str| ChannelInput {
+        name: name.into(),
+        entry: Channel {
+            category: "General".into(),
+            uuid: Channel::owned_uuid(alice_cell.agent_pubkey(), uuid::Uuid::new_v4().as_bytes()),
+        },
+    };
+    let message = |channel: &ChannelData, content: &str| MessageInput {
+        last_seen: LastSeen::First,
+        channel: channel.entry.clone(),
+        entry: Message {
+            uuid: uuid::Uuid::new_v4().to_string(),
+            content: content.into(),
+        },
+        signal: None,
+    };
+
+    // Everything before bobbo goes to sleep
+    let old_channel: ChannelData = conductor
+        .call(alice_chat, "create_channel", create_channel("Old"))
+        .await;
+    let _: MessageData = conductor
+        .call(
+            alice_chat,
+            "create_message",
+            message(&old_channel, "before sleep"),
+        )
+        .await;
+
+    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+    let since = Timestamp::now();
+    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+    // Everything while bobbo was away
+    let new_channel: ChannelData = conductor
+        .call(alice_chat, "create_channel", create_channel("New"))
+        .await;
+    let in_old: MessageData = conductor
+        .call(
+            alice_chat,
+            "create_message",
+            message(&old_channel, "while asleep"),
+        )
+        .await;
+    let in_new: MessageData = conductor
+        .call(
+            alice_chat,
+            "create_message",
+            message(&new_channel, "in the new channel"),
+        )
+        .await;
+    let _: () = conductor.call(alice_chat, "refresh_chatter", ()).await;
+
+    // TODO: add consistency awaiting to sweettest
+    tokio::time::sleep(tokio::time::Duration::from_millis(4000)).await;
+
+    let sync: SyncResult = conductor
+        .call(
+            bobbo_chat,
+            "sync_since",
+            SyncInput {
+                since,
+                category: "General".into(),
+            },
+        )
+        .await;
+
+    assert_eq!(sync.channels, vec![new_channel.clone()]);
+    let mut synced: Vec<_> = sync
+        .messages
+        .into_iter()
+        .map(|c| (c.channel.entry, c.messages))
+        .collect();
+    synced.sort_by_key(|(channel, _)| channel.clone());
+    let mut expected = vec![
+        (old_channel.entry, vec![in_old]),
+        (new_channel.entry, vec![in_new]),
+    ];
+    expected.sort_by_key(|(channel, _)| channel.clone());
+    assert_eq!(synced, expected);
+    assert_eq!(
+        sync.presence.joined,
+        vec![alice_cell.agent_pubkey().clone()]
+    );
+    assert!(sync.presence.left.is_empty());
+    assert!(sync.synced_at > since);
+}
@@ -0,0 +1,234 @@
+use chat::rate_limit::MAX_SLOW_MODE_SECONDS;
+use chat::validation::*;
+use chat::*;
+use hc_joining_code::Props;
+use holochain::conductor::api::error::{ConductorApiError, ConductorApiResult};
+use holochain::sweettest::*;
+
+fn alice() -> AgentPubKey {
+    AgentPubKey::from_raw_36(vec![1; 36])
+}
+
+fn bobbo() -> AgentPubKey {
+    AgentPubKey::from_raw_36(vec![2; 36])
+}
+
+fn now() -> Timestamp {
+    Timestamp::from_micros(1_640_995_200_000_000)
+}
+
+fn channel_info() -> ChannelInfo {
+    ChannelInfo {
+        category: "General".into(),
+        uuid: "5a9c3c4e-1a5b-4c1e-9f3a-2b8f2a6d7e10".into(),
+        name: "Test Ch".into(),
+        created_by: alice(),
+        created_at: now(),
+        archived: false,
+        slow_mode_seconds: None,
+        previous: None,
+    }
+}
+
+fn assert_invalid(result: ValidateCallbackResult) {
+    assert!(
+        matches!(result, ValidateCallbackResult::Invalid(_)),
+        "expected invalid, got {:?}",
+        result
+    );
+}
+
+#[test]
+fn channel_info_valid() {
+    assert_eq!(
+        validate_channel_info(&channel_info(), &alice(), now()),
+        ValidateCallbackResult::Valid
+    );
+}
+
+#[test]
+fn channel_info_forged_created_by() {
+    assert_invalid(validate_channel_info(&channel_info(), &bobbo(), now()));
+}
+
+#[test]
+fn channel_info_created_at_drift() {
+    let drift = (CHANNEL_INFO_TIMESTAMP_TOLERANCE_SECONDS + 1) * 1_000_000;
+    let mut info = channel_info();
+    info.created_at = Timestamp::from_micros(now().as_micros() - drift);
+    assert_invalid(validate_channel_info(&info, &alice(), now()));
+    info.created_at = Timestamp::from_micros(now().as_micros() + drift);
+    assert_invalid(validate_channel_info(&info, &alice(), now()));
+}
+
+#[test]
+fn channel_info_created_at_overflow() {
+    let mut info = channel_info();
+    info.created_at = Timestamp::from_micros(i64::MIN);
+    assert_invalid(validate_channel_info(&info, &alice(), now()));
+    info.created_at = Timestamp::from_micros(i64::MAX);
+    assert_invalid(validate_channel_info(
+        &info,
+        &alice(),
+        Timestamp::from_micros(-1),
+    ));
+}
+
+#[test]
+fn category_info_name_bounds() {
+    let mut info = CategoryInfo {
+        category: "General".into(),
+        name: std::iter::repeat('x')
+            .take(MAX_CATEGORY_NAME_LENGTH)
+            .collect(),
+        position: 0,
+        created_by: alice(),
+        created_at: now(),
+        previous: None,
+    };
+    assert_eq!(
+        validate_category_info(&info, &alice(), now()),
+        ValidateCallbackResult::Valid
+    );
+    info.name.push('x');
+    assert_invalid(validate_category_info(&info, &alice(), now()));
+    info.name = "General".into();
+    info.created_at = Timestamp::from_micros(i64::MIN);
+    assert_invalid(validate_category_info(&info, &alice(), now()));
+}
+
+#[test]
+fn channel_info_name_bounds() {
+    let mut info = channel_info();
+    info.name = "".into();
+    assert_invalid(validate_channel_info(&info, &alice(), now()));
+    info.name = "   ".into();
+    assert_invalid(validate_channel_info(&info, &alice(), now()));
+    info.name = std::iter::repeat('x')
+        .take(MAX_CHANNEL_NAME_LENGTH + 1)
+        .collect();
+    assert_invalid(validate_channel_info(&info, &alice(), now()));
+    info.name = std::iter::repeat('x')
+        .take(MAX_CHANNEL_NAME_LENGTH)
+        .collect();
+    assert_eq!(
+        validate_channel_info(&info, &alice(), now()),
+        ValidateCallbackResult::Valid
+    );
+}
+
+#[test]
+fn channel_info_category_bounds() {
+    let mut info = channel_info();
+    info.category = "".into();
+    assert_invalid(validate_channel_info(&info, &alice(), now()));
+    info.category = std::iter::repeat('x')
+        .take(MAX_CATEGORY_LENGTH + 1)
+        .collect();
+    assert_invalid(validate_channel_info(&info, &alice(), now()));
+}
+
+#[test]
+fn channel_info_bad_uuid() {
+    let mut info = channel_info();
+    info.uuid = "123".into();
+    assert_invalid(validate_channel_info(&info, &alice(), now()));
+}
+
+#[test]
+fn channel_info_slow_mode_bounds() {
+    let mut info = channel_info();
+    info.slow_mode_seconds = Some(MAX_SLOW_MODE_SECONDS);
+    assert_eq!(
+        validate_channel_info(&info, &alice(), now()),
+        ValidateCallbackResult::Valid
+    );
+    info.slow_mode_seconds = Some(MAX_SLOW_MODE_SECONDS + 1);
+    assert_invalid(validate_channel_info(&info, &alice(), now()));
+}
+
+#[test]
+fn message_length() {
+    let mut message = Message {
+        uuid: "msg".into(),
+        content: std::iter::repeat('x').take(MAX_MESSAGE_LENGTH).collect(),
+    };
+    assert_eq!(validate_message(&message), ValidateCallbackResult::Valid);
+    message.content.push('x');
+    assert_invalid(validate_message(&message));
+}
+
+#[test]
+fn only_original_author_deletes() {
+    assert_eq!(
+        validate_original_author(&alice(), &alice()),
+        ValidateCallbackResult::Valid
+    );
+    assert_invalid(validate_original_author(&bobbo(), &alice()));
+}
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn create_channel_rejects_bad_info() {
+    // Use prebuilt DNA bundle.
+    // You must build the DNA bundle as a separate step before running the test.
+    let dna_path = std::env::current_dir()
+        .unwrap()
+        .join("../../elemental-chat.dna");
+
+    let dna = SweetDnaFile::from_bundle_with_overrides(
+        &dna_path,
+        None,
+        Some(Props {
+            skip_proof: true,
+            holo_agent_override: None,
+            development_stage: None,
+            t_and_c: None,
+            t_and_c_agreement: None,
+        }),
+    )
+    .await
+    .unwrap();
+
+    let mut conductor = SweetConductor::from_standard_config().await;
+    let agents = SweetAgents::get(conductor.keystore(), 1).await;
+    let apps = conductor
+        .setup_app_for_agents("elemental-chat", &agents, &[dna])
+        .await
+        .unwrap();
+    let ((alice_cell,),) = apps.into_tuples();
+    let alice_chat = &alice_cell.zome("chat");
+
+    let empty_name: ConductorApiResult<ChannelData> = conductor
+        .call_fallible(
+            alice_chat,
+            "create_channel",
+            ChannelInput {
+                name: "".into(),
+                entry: Channel {
+                    category: "General".into(),
+                    uuid: Channel::owned_uuid(
+                        alice_cell.agent_pubkey(),
+                        uuid::Uuid::new_v4().as_bytes(),
+                    ),
+                },
+            },
+        )
+        .await;
+    assert!(matches!(empty_name, Err(ConductorApiError::CellError(_))));
+
+    let bad_uuid: ConductorApiResult<ChannelData> = conductor
+        .call_fallible(
+            alice_chat,
+            "create_channel",
+            ChannelInput {
+                name: "Test Ch".into(),
+                entry: Channel {
+                    category: "General".into(),
+                    uuid: "123".into(),
+                },
+            },
+        )
+        .await;
+    assert!(bad_uuid.is_err());
+}